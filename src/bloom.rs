@@ -0,0 +1,295 @@
+use crate::renderer::{HDR_FORMAT, UniformGroup};
+
+// number of half resolution steps the bright pixels are blurred across.
+const MIP_COUNT: u32 = 5;
+
+// blurs the parts of the HDR image above a threshold into a half resolution mip chain.
+// mip 0 of the chain holds the result, to be added to the image before tone mapping.
+pub(crate) struct Bloom {
+    uniform: UniformGroup,
+    source_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+
+    prefilter_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+
+    mip_views: Vec<wgpu::TextureView>,
+    // reads the HDR texture, for the prefilter pass.
+    hdr_bind_group: wgpu::BindGroup,
+    // one per mip, reading that mip.
+    mip_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl Bloom {
+    pub fn new(
+        device: &wgpu::Device,
+        hdr_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let mut uniform = UniformGroup::new(device, &[size_of::<f32>() as u64]);
+        uniform.add_bind_group(device);
+
+        let source_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Bloom source bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/bloom.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&uniform.bind_group_layout, &source_bind_group_layout],
+            immediate_size: 0,
+        });
+        let create_pipeline = |label, entry_point, blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_full"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend: Some(blend),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let prefilter_pipeline = create_pipeline(
+            "Bloom prefilter",
+            "fs_bloom_prefilter",
+            wgpu::BlendState::REPLACE,
+        );
+        let downsample_pipeline = create_pipeline(
+            "Bloom downsample",
+            "fs_bloom_blur",
+            wgpu::BlendState::REPLACE,
+        );
+        // each mip accumulates the blurred mip below it.
+        let upsample_pipeline = create_pipeline(
+            "Bloom upsample",
+            "fs_bloom_blur",
+            wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            },
+        );
+
+        let hdr_bind_group =
+            Self::create_source_bind_group(device, &source_bind_group_layout, &sampler, hdr_view);
+        let (mip_views, mip_bind_groups) =
+            Self::create_mips(device, &source_bind_group_layout, &sampler, width, height);
+
+        Self {
+            uniform,
+            source_bind_group_layout,
+            sampler,
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            mip_views,
+            hdr_bind_group,
+            mip_bind_groups,
+        }
+    }
+
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        hdr_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        self.hdr_bind_group = Self::create_source_bind_group(
+            device,
+            &self.source_bind_group_layout,
+            &self.sampler,
+            hdr_view,
+        );
+        (self.mip_views, self.mip_bind_groups) = Self::create_mips(
+            device,
+            &self.source_bind_group_layout,
+            &self.sampler,
+            width,
+            height,
+        );
+    }
+
+    // blurred bright pixels at half the HDR resolution.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.mip_views[0]
+    }
+
+    fn create_source_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bloom source bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    fn create_mips(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+    ) -> (Vec<wgpu::TextureView>, Vec<wgpu::BindGroup>) {
+        let size = wgpu::Extent3d {
+            width: (width / 2).max(1),
+            height: (height / 2).max(1),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("bloom"),
+            size,
+            mip_level_count: MIP_COUNT.min(size.max_mips(wgpu::TextureDimension::D2)),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let views: Vec<wgpu::TextureView> = (0..texture.mip_level_count())
+            .map(|mip| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let bind_groups = views
+            .iter()
+            .map(|view| Self::create_source_bind_group(device, layout, sampler, view))
+            .collect();
+        (views, bind_groups)
+    }
+
+    pub fn render(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        threshold: f32,
+    ) {
+        self.uniform
+            .write(queue, 0, &[bytemuck::cast_slice(&[threshold])]);
+
+        let mip_count = self.mip_views.len();
+        self.render_pass(
+            command_encoder,
+            &self.prefilter_pipeline,
+            &self.hdr_bind_group,
+            0,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        );
+        for mip in 1..mip_count {
+            self.render_pass(
+                command_encoder,
+                &self.downsample_pipeline,
+                &self.mip_bind_groups[mip - 1],
+                mip,
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            );
+        }
+        for mip in (0..mip_count - 1).rev() {
+            self.render_pass(
+                command_encoder,
+                &self.upsample_pipeline,
+                &self.mip_bind_groups[mip + 1],
+                mip,
+                wgpu::LoadOp::Load,
+            );
+        }
+    }
+
+    fn render_pass(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::BindGroup,
+        target_mip: usize,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Bloom"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.mip_views[target_mip],
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        render_pass.set_pipeline(pipeline);
+        self.uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, source, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+
+use glam::{Vec2, Vec3};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta};
+
+use crate::key_bindings::Action;
+use crate::model::{Camera, CameraMode};
+
+// pixels of a touchpad scroll that count as one line of a mouse wheel.
+const PIXELS_PER_LINE: f32 = 40.0;
+// keeps the orbiting camera off the poles, where the view would flip.
+const ORBIT_PITCH_LIMIT: f32 = std::f32::consts::PI * 0.49;
+// zooming out stops at about 170°, the projection degenerates as the field of view nears 180°.
+const MAX_YFOV: f32 = std::f32::consts::PI * 0.95;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ControlMode {
+    // moves with the movement actions, turns with the mouse and scrolling zooms.
+    Fly,
+    // turns around `pivot` while the left button is dragged, `distance` away from it. middle drags
+    // and the movement actions move the pivot, scrolling moves towards it.
+    Orbit { pivot: Vec3, distance: f32 },
+}
+
+// moves a camera from the actions of held keys, mouse motion and analog sticks.
+pub(crate) struct CameraController {
+    // units per second.
+    pub speed: f32,
+    // speed multiplier while sprinting.
+    pub sprint_multiplier: f32,
+    // how quickly the velocity approaches the held movement's, per second.
+    pub acceleration: f32,
+    // how quickly the velocity decays once no movement is held, per second.
+    pub damping: f32,
+    // radians per pixel of mouse motion.
+    pub mouse_sensitivity: f32,
+    // time constant of the mouse look filter in seconds. 0 turns with the raw motion.
+    pub mouse_smoothing: f32,
+    // radians per second of the look actions.
+    pub rotation_speed: f32,
+    // radians of field of view per second of the zoom actions.
+    pub zoom_speed: f32,
+    // moving forward goes where the camera looks, instead of level with the ground.
+    pub free_flight: bool,
+    // fraction of the field of view a line of scrolling narrows it by, or of the orbit distance
+    // it moves towards the pivot.
+    pub scroll_zoom_step: f32,
+
+    mode: ControlMode,
+    pressed_actions: HashSet<Action>,
+    pressed_buttons: HashSet<MouseButton>,
+    // world space, units per second.
+    velocity: Vec3,
+    // filtered mouse motion in pixels per second.
+    mouse_velocity: Vec2,
+    // accumulated since the last `update`.
+    mouse_motion: (f64, f64),
+    // lines scrolled since the last `update`, positive away from the user.
+    scroll: f32,
+    // analog input in -1..1, held until replaced. x is right, y is up and z is forward.
+    analog_movement: Vec3,
+    // x turns right, y looks up.
+    analog_look: Vec2,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            speed: 10.0,
+            sprint_multiplier: 3.0,
+            acceleration: 10.0,
+            damping: 10.0,
+            mouse_sensitivity: 0.002,
+            mouse_smoothing: 0.02,
+            rotation_speed: 1.0,
+            zoom_speed: 0.5,
+            free_flight: false,
+            scroll_zoom_step: 0.1,
+            mode: ControlMode::Fly,
+            pressed_actions: HashSet::new(),
+            pressed_buttons: HashSet::new(),
+            velocity: Vec3::ZERO,
+            mouse_velocity: Vec2::ZERO,
+            mouse_motion: (0.0, 0.0),
+            scroll: 0.0,
+            analog_movement: Vec3::ZERO,
+            analog_look: Vec2::ZERO,
+        }
+    }
+}
+
+impl CameraController {
+    pub fn is_orbiting(&self) -> bool {
+        matches!(self.mode, ControlMode::Orbit { .. })
+    }
+
+    // turns `camera` towards `pivot` and orbits around it from where the camera is.
+    pub fn orbit(&mut self, camera: &mut Camera, pivot: Vec3) {
+        let offset = pivot - camera.position;
+        let distance = offset.length();
+        if distance > 0.0 {
+            let direction = offset / distance;
+            camera.yaw = f32::atan2(-direction.x, -direction.z);
+            camera.pitch = direction.y.asin();
+        }
+        camera.roll = 0.0;
+        self.mode = ControlMode::Orbit {
+            pivot,
+            distance: distance.max(camera.znear),
+        };
+        self.velocity = Vec3::ZERO;
+    }
+
+    pub fn fly(&mut self) {
+        self.mode = ControlMode::Fly;
+        self.velocity = Vec3::ZERO;
+    }
+
+    pub fn process_keyboard(&mut self, action: Action, state: ElementState) {
+        if state.is_pressed() {
+            self.pressed_actions.insert(action);
+        } else {
+            self.pressed_actions.remove(&action);
+        }
+    }
+
+    pub fn process_mouse(&mut self, delta: (f64, f64)) {
+        self.mouse_motion.0 += delta.0;
+        self.mouse_motion.1 += delta.1;
+    }
+
+    pub fn process_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        if state.is_pressed() {
+            self.pressed_buttons.insert(button);
+        } else {
+            self.pressed_buttons.remove(&button);
+        }
+    }
+
+    pub fn process_scroll(&mut self, delta: MouseScrollDelta) {
+        self.scroll += match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+        };
+    }
+
+    // moves like the movement actions and turns like the look actions, scaled by how far the sticks are pushed.
+    #[cfg_attr(not(feature = "gamepad"), allow(unused))]
+    pub fn process_gamepad(&mut self, movement: Vec3, look: Vec2) {
+        self.analog_movement = movement;
+        self.analog_look = look;
+    }
+
+    fn is_pressed(&self, action: Action) -> bool {
+        self.pressed_actions.contains(&action)
+    }
+
+    // applies the input since the last update, `dt` seconds later.
+    pub fn update(&mut self, camera: &mut Camera, dt: f32) {
+        let mut speed = self.speed;
+        if self.is_pressed(Action::Sprint) {
+            speed *= self.sprint_multiplier;
+        }
+        let forward_dir = if self.free_flight && camera.mode == CameraMode::Normal {
+            camera.direction()
+        } else {
+            camera.forward_vec()
+        };
+        // strafes along the view's right, which the roll tilts.
+        let right_dir = camera.right_vec();
+
+        let mut dir = Vec3::ZERO;
+        if self.is_pressed(Action::Forward) {
+            dir += forward_dir;
+        }
+        if self.is_pressed(Action::Back) {
+            dir -= forward_dir;
+        }
+        if self.is_pressed(Action::Left) {
+            dir -= right_dir;
+        }
+        if self.is_pressed(Action::Right) {
+            dir += right_dir;
+        }
+        if self.is_pressed(Action::Up) {
+            dir += Vec3::Y;
+        }
+        if self.is_pressed(Action::Down) {
+            dir += Vec3::NEG_Y;
+        }
+
+        let analog_dir = right_dir * self.analog_movement.x
+            + Vec3::Y * self.analog_movement.y
+            + forward_dir * self.analog_movement.z;
+        let target_velocity = (dir.normalize_or_zero() + analog_dir).clamp_length_max(1.0) * speed;
+        let rate = if target_velocity == Vec3::ZERO {
+            self.damping
+        } else {
+            self.acceleration
+        };
+        self.velocity = target_velocity + (self.velocity - target_velocity) * (-rate * dt).exp();
+
+        let velocity = self.velocity * dt;
+        match (&mut self.mode, camera.mode) {
+            (ControlMode::Orbit { pivot, .. }, _) => *pivot += velocity,
+            (ControlMode::Fly, CameraMode::Normal) => camera.position += velocity,
+            // height keys raise the top-down view instead of the camera.
+            (ControlMode::Fly, CameraMode::TopDown) => {
+                camera.position += velocity.with_y(0.0);
+                camera.top_down_height = f32::max(camera.top_down_height + velocity.y, 0.1);
+            }
+        }
+
+        let mouse_motion = Vec2::new(self.mouse_motion.0 as f32, self.mouse_motion.1 as f32);
+        self.mouse_motion = (0.0, 0.0);
+        let mouse_motion = if self.mouse_smoothing > 0.0 && dt > 0.0 {
+            // filtered as a rate, so the response doesn't depend on the frame rate.
+            let mouse_velocity = mouse_motion / dt;
+            self.mouse_velocity = mouse_velocity
+                + (self.mouse_velocity - mouse_velocity) * (-dt / self.mouse_smoothing).exp();
+            self.mouse_velocity * dt
+        } else {
+            mouse_motion
+        };
+        let scroll = std::mem::take(&mut self.scroll);
+        let scroll_zoom = (1.0 - self.scroll_zoom_step).powf(scroll);
+        match &mut self.mode {
+            ControlMode::Fly => {
+                camera.yaw -= self.mouse_sensitivity * mouse_motion.x;
+                camera.pitch -= self.mouse_sensitivity * mouse_motion.y;
+                camera.yfov *= scroll_zoom;
+            }
+            ControlMode::Orbit { pivot, distance } => {
+                if self.pressed_buttons.contains(&MouseButton::Left) {
+                    camera.yaw -= self.mouse_sensitivity * mouse_motion.x;
+                    camera.pitch -= self.mouse_sensitivity * mouse_motion.y;
+                }
+                // the point under the cursor follows it at the pivot's distance.
+                if self.pressed_buttons.contains(&MouseButton::Middle) {
+                    let right = camera.right_vec();
+                    let up = right.cross(camera.direction());
+                    let pan = self.mouse_sensitivity * *distance * mouse_motion;
+                    *pivot += up * pan.y - right * pan.x;
+                }
+                *distance = f32::max(*distance * scroll_zoom, camera.znear);
+            }
+        }
+
+        let rotation = self.rotation_speed * dt;
+        if self.is_pressed(Action::LookLeft) {
+            camera.yaw += rotation;
+        }
+        if self.is_pressed(Action::LookRight) {
+            camera.yaw -= rotation;
+        }
+        if self.is_pressed(Action::LookUp) {
+            camera.pitch += rotation;
+        }
+        if self.is_pressed(Action::LookDown) {
+            camera.pitch -= rotation;
+        }
+        if self.is_pressed(Action::RollLeft) {
+            camera.roll += rotation;
+        }
+        if self.is_pressed(Action::RollRight) {
+            camera.roll -= rotation;
+        }
+        camera.yaw -= self.analog_look.x * rotation;
+        camera.pitch += self.analog_look.y * rotation;
+        let pitch_limit = match self.mode {
+            ControlMode::Fly => std::f32::consts::PI * 0.5,
+            ControlMode::Orbit { .. } => ORBIT_PITCH_LIMIT,
+        };
+        camera.pitch = f32::clamp(camera.pitch, -pitch_limit, pitch_limit);
+        if let ControlMode::Orbit { pivot, distance } = self.mode {
+            camera.position = pivot - camera.direction() * distance;
+        }
+
+        if self.is_pressed(Action::ZoomOut) {
+            camera.yfov += self.zoom_speed * dt
+        }
+        if self.is_pressed(Action::ZoomIn) {
+            camera.yfov -= self.zoom_speed * dt
+        }
+        camera.yfov = f32::clamp(camera.yfov, 0.01, MAX_YFOV);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_moves_along_forward_vec() {
+        let mut controller = CameraController::default();
+        let mut camera = Camera {
+            yaw: 0.7,
+            pitch: 0.3,
+            ..Default::default()
+        };
+        controller.process_keyboard(Action::Forward, ElementState::Pressed);
+        for _ in 0..10 {
+            controller.update(&mut camera, 0.1);
+        }
+
+        let moved = camera.position;
+        assert!(moved.length() > 1.0);
+        // level with the ground, not where the pitched camera looks.
+        assert!(moved.normalize().abs_diff_eq(camera.forward_vec(), 1e-5));
+
+        // released, the camera comes to a stop.
+        controller.process_keyboard(Action::Forward, ElementState::Released);
+        for _ in 0..100 {
+            controller.update(&mut camera, 0.1);
+        }
+        let stopped = camera.position;
+        controller.update(&mut camera, 0.1);
+        assert!(camera.position.abs_diff_eq(stopped, 1e-5));
+    }
+
+    #[test]
+    fn mouse_look() {
+        let mut controller = CameraController {
+            mouse_smoothing: 0.0,
+            ..Default::default()
+        };
+        let mut camera = Camera::default();
+        // split over two events, both count.
+        controller.process_mouse((60.0, -20.0));
+        controller.process_mouse((40.0, -30.0));
+        controller.update(&mut camera, 0.016);
+
+        // right turns right, which is a smaller yaw, and up looks up.
+        assert!((camera.yaw - -100.0 * controller.mouse_sensitivity).abs() < 1e-6);
+        assert!((camera.pitch - 50.0 * controller.mouse_sensitivity).abs() < 1e-6);
+        assert_eq!(camera.position, Vec3::ZERO);
+
+        // the motion is used up.
+        controller.update(&mut camera, 0.016);
+        assert!((camera.yaw - -100.0 * controller.mouse_sensitivity).abs() < 1e-6);
+
+        // looking straight up is as far as it goes.
+        controller.process_mouse((0.0, -10_000.0));
+        controller.update(&mut camera, 0.016);
+        assert_eq!(camera.pitch, std::f32::consts::PI * 0.5);
+    }
+
+    #[test]
+    fn scroll_zoom_is_capped() {
+        let mut controller = CameraController::default();
+        let mut camera = Camera::default();
+        controller.process_scroll(MouseScrollDelta::LineDelta(0.0, 1.0));
+        controller.update(&mut camera, 0.016);
+        assert!((camera.yfov - (1.0 - controller.scroll_zoom_step)).abs() < 1e-6);
+
+        // scrolling towards the user widens the view, up to the cap.
+        controller.process_scroll(MouseScrollDelta::LineDelta(0.0, -100.0));
+        controller.update(&mut camera, 0.016);
+        assert_eq!(camera.yfov, MAX_YFOV);
+    }
+}
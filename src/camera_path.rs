@@ -0,0 +1,102 @@
+use crate::model::{Camera, Transform};
+
+// seconds between a loaded path's last keyframe and the first one added to it.
+const LOADED_PATH_GAP: f32 = 1.0;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct CameraKeyframe {
+    // seconds from the first keyframe.
+    pub time: f32,
+    pub transform: Transform,
+}
+
+// camera poses recorded along the way, played back as a flythrough that moves between them
+// in the time they were recorded apart.
+#[derive(Default)]
+pub(crate) struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    // clock time of the first keyframe, None while empty or loaded.
+    start: Option<f32>,
+    // seconds into the playback, None while not playing.
+    playback: Option<f32>,
+}
+
+impl CameraPath {
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    // adds the camera's pose as the last keyframe. `time` is in seconds on any steady clock.
+    pub fn add_keyframe(&mut self, camera: &Camera, time: f32) {
+        let start = *self.start.get_or_insert(match self.keyframes.last() {
+            Some(last) => time - last.time - LOADED_PATH_GAP,
+            None => time,
+        });
+        self.keyframes.push(CameraKeyframe {
+            time: time - start,
+            transform: camera.transform(),
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.start = None;
+        self.playback = None;
+    }
+
+    // starts from the first keyframe. paths need two keyframes to go anywhere.
+    pub fn play(&mut self) {
+        self.playback = (self.keyframes.len() >= 2).then_some(0.0);
+    }
+
+    pub fn stop(&mut self) {
+        self.playback = None;
+    }
+
+    // moves the camera `dt` seconds further along the path while playing, and stops at the end.
+    pub fn update(&mut self, camera: &mut Camera, dt: f32) {
+        let Some(time) = self.playback.map(|time| time + dt) else {
+            return;
+        };
+        camera.set_transform(&self.sample(time));
+        self.playback = (time < self.keyframes.last().unwrap().time).then_some(time);
+    }
+
+    // clamped outside the keyframes.
+    fn sample(&self, time: f32) -> Transform {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return self.keyframes[0].transform.clone();
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].transform.clone();
+        }
+        let (prev, next) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let t = (time - prev.time) / (next.time - prev.time);
+        prev.transform.lerp(&next.transform, t)
+    }
+
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, &self.keyframes)?;
+        Ok(())
+    }
+
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(Self {
+            keyframes: serde_json::from_reader(file)?,
+            start: None,
+            playback: None,
+        })
+    }
+}
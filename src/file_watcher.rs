@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+
+use notify::{RecursiveMode, Watcher};
+
+// reports changes to a file, to reload it while it's edited.
+pub(crate) struct FileWatcher {
+    // stops watching when dropped.
+    _watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    path: PathBuf,
+}
+
+impl FileWatcher {
+    pub fn new(path: impl AsRef<Path>) -> notify::Result<Self> {
+        let path = std::path::absolute(path.as_ref())?;
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        // editors often save by replacing the file, which a watch on the file itself wouldn't see.
+        if let Some(dir) = path.parent() {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            path,
+        })
+    }
+
+    // whether the file was written or replaced since the last call.
+    pub fn changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter().flatten() {
+            changed |= (event.kind.is_create() || event.kind.is_modify())
+                && event.paths.contains(&self.path);
+        }
+        changed
+    }
+}
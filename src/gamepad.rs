@@ -0,0 +1,37 @@
+use gilrs::{Axis, Button, Gilrs};
+use glam::{Vec2, Vec3};
+
+// camera control from every connected gamepad: the left stick moves, the right stick looks
+// around and the right/left triggers move up/down.
+pub(crate) struct Gamepad {
+    gilrs: Gilrs,
+}
+
+impl Gamepad {
+    // None if gamepads aren't supported on this platform.
+    pub fn new() -> Option<Self> {
+        Gilrs::new().ok().map(|gilrs| Self { gilrs })
+    }
+
+    // call once per frame, before `CameraController::update`.
+    pub fn poll(&mut self, camera_controller: &mut crate::camera_controller::CameraController) {
+        // the events only matter for updating the gamepad state, with gilrs' dead zones applied.
+        while self.gilrs.next_event().is_some() {}
+
+        let mut movement = Vec3::ZERO;
+        let mut look = Vec2::ZERO;
+        for (_, gamepad) in self.gilrs.gamepads() {
+            let trigger = |button| gamepad.button_data(button).map_or(0.0, |data| data.value());
+            movement += Vec3::new(
+                gamepad.value(Axis::LeftStickX),
+                trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2),
+                gamepad.value(Axis::LeftStickY),
+            );
+            look += Vec2::new(
+                gamepad.value(Axis::RightStickX),
+                gamepad.value(Axis::RightStickY),
+            );
+        }
+        camera_controller.process_gamepad(movement, look);
+    }
+}
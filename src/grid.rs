@@ -0,0 +1,95 @@
+use crate::renderer::{HDR_FORMAT, UniformGroup};
+use glam::{Mat4, Vec3};
+
+// lines beyond this many cells from the camera fade out completely.
+const FADE_CELLS: f32 = 100.0;
+
+// an infinite reference grid on the XZ plane, blended in the main pass after the opaque primitives.
+// it writes the depth of the plane, so geometry in front of it hides it.
+pub(crate) struct Grid {
+    uniform: UniformGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Grid {
+    // `sample_count` must match the main pass.
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
+        let mut uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<Mat4>() as u64,
+                size_of::<Mat4>() as u64,
+                size_of::<Vec3>() as u64,
+                // spacing and fade distance.
+                size_of::<[f32; 2]>() as u64,
+            ],
+        );
+        uniform.add_bind_group(device);
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/grid.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&uniform.bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Grid"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_grid"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_grid"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // tested against the opaque primitives but doesn't occlude the transparent ones.
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::GreaterEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview_mask: None,
+            cache: None,
+        });
+
+        Self { uniform, pipeline }
+    }
+
+    // call before the pass `draw` is recorded into. `color` is linear HDR, like the lit scene.
+    pub fn write(&self, queue: &wgpu::Queue, camera_matrix: Mat4, spacing: f32, color: Vec3) {
+        self.uniform.write(
+            queue,
+            0,
+            &[
+                bytemuck::cast_slice(&[camera_matrix]),
+                bytemuck::cast_slice(&[camera_matrix.inverse()]),
+                bytemuck::cast_slice(&[color]),
+                bytemuck::cast_slice(&[spacing, spacing * FADE_CELLS]),
+            ],
+        );
+    }
+
+    // replaces the bind group at 0, so rebind it before drawing anything else.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        self.uniform.set(render_pass, 0, 0);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use winit::keyboard::KeyCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Action {
+    Forward,
+    Back,
+    Left,
+    Right,
+    Up,
+    Down,
+    Sprint,
+    LookLeft,
+    LookRight,
+    LookUp,
+    LookDown,
+    RollLeft,
+    RollRight,
+    ZoomIn,
+    ZoomOut,
+    // shows the cursor while held.
+    ToggleCursor,
+}
+
+// which action each key triggers. an action can have any number of keys.
+pub(crate) struct KeyBindings {
+    actions: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            actions: HashMap::from([
+                (KeyCode::KeyW, Action::Forward),
+                (KeyCode::KeyS, Action::Back),
+                (KeyCode::KeyA, Action::Left),
+                (KeyCode::KeyD, Action::Right),
+                (KeyCode::Space, Action::Up),
+                (KeyCode::ShiftLeft, Action::Down),
+                (KeyCode::ControlLeft, Action::Sprint),
+                (KeyCode::ControlRight, Action::Sprint),
+                (KeyCode::ArrowLeft, Action::LookLeft),
+                (KeyCode::ArrowRight, Action::LookRight),
+                (KeyCode::ArrowUp, Action::LookUp),
+                (KeyCode::ArrowDown, Action::LookDown),
+                (KeyCode::KeyQ, Action::RollLeft),
+                (KeyCode::KeyE, Action::RollRight),
+                (KeyCode::Equal, Action::ZoomIn),
+                (KeyCode::Minus, Action::ZoomOut),
+                (KeyCode::AltLeft, Action::ToggleCursor),
+            ]),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.actions.get(&code).copied()
+    }
+}
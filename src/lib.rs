@@ -0,0 +1,22 @@
+//! Physically based rasterizer on wgpu.
+//!
+//! Load a [`Scene`] from a glTF or OBJ file, create a [`Renderer`] for the device and output,
+//! and call [`Renderer::render_to`] once per frame with the view to draw into.
+//! The renderer only borrows the device and queue, so it can be driven from any event loop.
+
+mod bloom;
+mod fxaa;
+mod grid;
+mod light_culling;
+mod light_gizmos;
+pub mod model;
+pub mod renderer;
+mod skybox;
+mod ssao;
+pub mod text;
+pub mod texture;
+mod transmission;
+
+pub use model::{Aabb, Camera, Scene, SceneError};
+pub use renderer::{DebugView, HDR_FORMAT, RenderStats, Renderer, SkyGradient, ToneMapping};
+pub use texture::{ReadbackError, Texture};
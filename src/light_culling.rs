@@ -0,0 +1,215 @@
+use crate::renderer::UniformGroup;
+use glam::{Mat4, Vec3};
+
+// screen space size of a tile in pixels. must match light_culling.wgsl and shader.wgsl
+const TILE_SIZE: u32 = 16;
+// lights past this are dropped from a tile. must match light_culling.wgsl and shader.wgsl
+const MAX_LIGHTS_PER_TILE: u32 = 63;
+
+// tiled forward lighting: lists the lights reaching into each tile of the screen,
+// so the lit pass only shades a pixel with the lights of its tile.
+// tiles reach as deep as the prepass G-buffer, so `render` runs between the prepass and the lit pass.
+pub(crate) struct LightCulling {
+    uniform: UniformGroup,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+
+    // per tile, the number of lights followed by `MAX_LIGHTS_PER_TILE` light indices.
+    tile_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    tile_columns: u32,
+    tile_rows: u32,
+}
+
+impl LightCulling {
+    // `position_view` is the prepass position target and `light_buffer` the lights the lit pass reads.
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        position_view: &wgpu::TextureView,
+        light_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let mut uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<Mat4>() as u64,
+                size_of::<Vec3>() as u64,
+                size_of::<Vec3>() as u64,
+                size_of::<u32>() as u64,
+                size_of::<f32>() as u64,
+            ],
+        );
+        uniform.add_bind_group(device);
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Light culling bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, false),
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/light_culling.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&uniform.bind_group_layout, &bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Light culling"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cs_cull"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let (tile_columns, tile_rows) = Self::tile_count(width, height);
+        let tile_buffer = Self::create_tile_buffer(device, tile_columns, tile_rows);
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            position_view,
+            light_buffer,
+            &tile_buffer,
+        );
+
+        Self {
+            uniform,
+            bind_group_layout,
+            pipeline,
+            tile_buffer,
+            bind_group,
+            tile_columns,
+            tile_rows,
+        }
+    }
+
+    // also called when the inputs are recreated, with the same size.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        position_view: &wgpu::TextureView,
+        light_buffer: &wgpu::Buffer,
+    ) {
+        (self.tile_columns, self.tile_rows) = Self::tile_count(width, height);
+        self.tile_buffer = Self::create_tile_buffer(device, self.tile_columns, self.tile_rows);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            position_view,
+            light_buffer,
+            &self.tile_buffer,
+        );
+    }
+
+    fn tile_count(width: u32, height: u32) -> (u32, u32) {
+        (width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE))
+    }
+
+    fn create_tile_buffer(
+        device: &wgpu::Device,
+        tile_columns: u32,
+        tile_rows: u32,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("tile lights"),
+            size: (tile_columns * tile_rows * (MAX_LIGHTS_PER_TILE + 1)) as u64
+                * size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        position_view: &wgpu::TextureView,
+        light_buffer: &wgpu::Buffer,
+        tile_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Light culling bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(position_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: tile_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    // the light lists `render` writes, indexed by tile row * `tile_columns` + tile column.
+    pub fn tile_buffer(&self) -> &wgpu::Buffer {
+        &self.tile_buffer
+    }
+
+    pub fn tile_columns(&self) -> u32 {
+        self.tile_columns
+    }
+
+    // lights reach until their irradiance falls below `light_cutoff`.
+    pub fn render(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        camera: &crate::model::Camera,
+        camera_matrix: Mat4,
+        light_count: u32,
+        light_cutoff: f32,
+    ) {
+        let inverse_camera = (camera_matrix * Mat4::from_translation(camera.eye())).inverse();
+        self.uniform.write(
+            queue,
+            0,
+            &[
+                bytemuck::cast_slice(&[inverse_camera]),
+                bytemuck::cast_slice(&[camera.eye()]),
+                bytemuck::cast_slice(&[camera.direction()]),
+                bytemuck::cast_slice(&[light_count]),
+                bytemuck::cast_slice(&[light_cutoff]),
+            ],
+        );
+
+        let mut compute_pass = command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Light culling"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &self.uniform.bind_groups[0].1, &[]);
+        compute_pass.set_bind_group(1, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.tile_columns, self.tile_rows, 1);
+    }
+}
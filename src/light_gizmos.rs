@@ -0,0 +1,186 @@
+use crate::model::Light;
+use glam::{Quat, Vec3};
+
+// world space size of the shapes.
+const GIZMO_SIZE: f32 = 0.25;
+// line segments per circle.
+const CIRCLE_SEGMENTS: usize = 16;
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+struct GizmoVertex {
+    position: Vec3,
+    color: Vec3,
+}
+
+impl GizmoVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+}
+
+// wireframe shapes showing where the scene's lights are: a sphere for point lights, a cone for
+// spot lights and an arrow pointing at the origin for directional lights, in the light's color.
+// drawn over everything at the end of the main pass.
+pub(crate) struct LightGizmos {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+}
+
+impl LightGizmos {
+    // `scene_bind_group_layout` is the scene uniform's, of which only the camera matrix is read.
+    // `sample_count` must match the main pass.
+    pub fn new(
+        device: &wgpu::Device,
+        scene_bind_group_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/light_gizmos.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[scene_bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light gizmos"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_gizmo"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<GizmoVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &GizmoVertex::ATTRIBS,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_gizmo"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: crate::renderer::HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // visible through geometry, lights are often inside or behind something.
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview_mask: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer: Self::create_vertex_buffer(device, 0),
+            vertex_count: 0,
+        }
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("light gizmos"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, lights: &[Light]) {
+        let mut vertices = Vec::new();
+        for light in lights {
+            match *light {
+                Light::Point { pos, color, .. } => {
+                    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                        circle(&mut vertices, pos, axis, GIZMO_SIZE, color);
+                    }
+                }
+                Light::Directional {
+                    direction, color, ..
+                } => {
+                    let direction = direction.normalize();
+                    let start = -direction * GIZMO_SIZE * 4.0;
+                    line(&mut vertices, start, Vec3::ZERO, color);
+                    // four barbs around the tip.
+                    let rotation = Quat::from_rotation_arc(Vec3::NEG_Z, direction);
+                    for barb in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y] {
+                        let end = rotation * (barb + Vec3::Z) * GIZMO_SIZE * 0.5;
+                        line(&mut vertices, Vec3::ZERO, end, color);
+                    }
+                }
+                Light::Spot {
+                    pos,
+                    direction,
+                    color,
+                    outer_cone_angle,
+                    ..
+                } => {
+                    let direction = direction.normalize();
+                    let length = GIZMO_SIZE * 4.0;
+                    let center = pos + direction * length;
+                    let radius = length * outer_cone_angle.tan();
+                    circle(&mut vertices, center, direction, radius, color);
+                    let (tangent, bitangent) = direction.any_orthonormal_pair();
+                    for edge in [tangent, -tangent, bitangent, -bitangent] {
+                        line(&mut vertices, pos, center + edge * radius, color);
+                    }
+                }
+            }
+        }
+
+        let size = size_of_val(vertices.as_slice()) as u64;
+        if self.vertex_buffer.size() < size {
+            self.vertex_buffer = Self::create_vertex_buffer(device, size.next_power_of_two());
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    // expects the scene uniform at group 0.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.vertex_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
+
+fn line(vertices: &mut Vec<GizmoVertex>, start: Vec3, end: Vec3, color: Vec3) {
+    vertices.push(GizmoVertex {
+        position: start,
+        color,
+    });
+    vertices.push(GizmoVertex {
+        position: end,
+        color,
+    });
+}
+
+// around `normal`.
+fn circle(vertices: &mut Vec<GizmoVertex>, center: Vec3, normal: Vec3, radius: f32, color: Vec3) {
+    let (tangent, bitangent) = normal.any_orthonormal_pair();
+    let point = |i: usize| {
+        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+        center + (tangent * angle.cos() + bitangent * angle.sin()) * radius
+    };
+    for i in 0..CIRCLE_SEGMENTS {
+        line(vertices, point(i), point(i + 1), color);
+    }
+}
@@ -1,64 +1,159 @@
-use std::{collections::HashSet, sync::Arc, time::Instant};
+use std::{collections::VecDeque, sync::Arc, time::Duration};
 
-use glam::Vec3;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// std's clock panics in the browser.
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use model::CameraMode;
+use renderer::{DebugView, ToneMapping};
 use winit::{
     event::{ElementState, KeyEvent, WindowEvent},
     keyboard::{KeyCode, PhysicalKey},
 };
 
-mod model;
-mod renderer;
-mod texture;
+use pbrpbr::{model, renderer, text, texture};
 
-struct Context {
+mod camera_controller;
+mod camera_path;
+#[cfg(not(target_arch = "wasm32"))]
+mod file_watcher;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod key_bindings;
+#[cfg(not(target_arch = "wasm32"))]
+mod scene_loader;
+
+// loaded at startup, and reloaded when it changes.
+const SCENE_PATH: &str = "res/scene2.glb";
+// where F8 saves the camera and F9 loads it from.
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+const CAMERA_PATH: &str = "camera.json";
+// where F10 saves the recorded flythrough and F11 loads it from.
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+const FLYTHROUGH_PATH: &str = "flythrough.json";
+// debug builds reload the lighting shader from the source tree when it changes.
+// release builds only have the shader embedded in the renderer.
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+const SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader/shader.wgsl");
+// requested MSAA sample count.
+const SAMPLE_COUNT: u32 = 4;
+// size of the frame rendered by `--headless`, and of the canvas in the browser.
+const HEADLESS_WIDTH: u32 = 1280;
+const HEADLESS_HEIGHT: u32 = 720;
+// frames averaged by the frame time overlay.
+const FRAME_TIME_FRAMES: usize = 60;
+// stops the exposure keys add or remove.
+const EXPOSURE_STEP: f32 = 0.5;
+// radians the sun keys turn or raise the sun by.
+const SUN_ANGLE_STEP: f32 = std::f32::consts::PI / 36.0;
+// factor the sun brightness keys scale its flux by, half a stop.
+const SUN_FLUX_STEP: f32 = std::f32::consts::SQRT_2;
+// the number keys select the debug view at the same index of `DebugView::ALL`.
+const DEBUG_VIEW_KEYS: [KeyCode; DebugView::ALL.len()] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+// the window a context presents to.
+struct WindowSurface {
     window: Arc<winit::window::Window>,
     surface: wgpu::Surface<'static>,
+    // supported by the surface on this adapter.
+    present_modes: Vec<wgpu::PresentMode>,
+}
+
+struct Context {
+    // None for headless contexts, which only render offscreen.
+    window_surface: Option<WindowSurface>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface_configuration: wgpu::SurfaceConfiguration,
 
     renderer: renderer::Renderer,
+    text_overlay: text::TextOverlay,
 
+    // empty until the first load is done.
     scene: model::Scene,
+    // the scene being loaded in the background, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    scene_loader: Option<scene_loader::SceneLoader>,
+    // None if the scene file can't be watched.
+    #[cfg(not(target_arch = "wasm32"))]
+    scene_watcher: Option<file_watcher::FileWatcher>,
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    shader_watcher: Option<file_watcher::FileWatcher>,
 
     cursor_visible: bool,
+    // in the window, None until the cursor moves over it.
+    cursor_position: Option<winit::dpi::PhysicalPosition<f64>>,
     focused: bool,
-    pressed_key: HashSet<KeyCode>,
-    mouse_motion: (f64, f64),
-    frame_instant: std::time::Instant,
+    key_bindings: key_bindings::KeyBindings,
+    camera_controller: camera_controller::CameraController,
+    camera_path: camera_path::CameraPath,
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<gamepad::Gamepad>,
+    frame_instant: Instant,
     time: u64,
+    // durations of the latest frames, oldest first.
+    frame_times: VecDeque<Duration>,
+    show_frame_time: bool,
+    // fits the camera's clip planes to the scene every frame, until they're moved by hand.
+    fit_clip_planes: bool,
+
+    // shown in the title, updated when it changes.
+    culled_draws: usize,
+    // latest size from `WindowEvent::Resized`. dragging the window sends many of them,
+    // only the last one is applied, once per frame.
+    pending_size: Option<winit::dpi::PhysicalSize<u32>>,
+    // a minimized window has a zero size, which no surface can be configured with.
+    // nothing is rendered until it's resized again.
+    minimized: bool,
 }
 
-impl Context {
-    async fn new(window: Arc<winit::window::Window>) -> Self {
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
-            ..Default::default()
-        });
-        let surface = instance.create_surface(window.clone()).unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await
-            .unwrap();
+#[derive(Debug)]
+enum ContextError {
+    Surface(wgpu::CreateSurfaceError),
+    Adapter(wgpu::RequestAdapterError),
+    Device(wgpu::RequestDeviceError),
+}
 
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features {
-                    features_wgpu: wgpu::FeaturesWGPU::TEXTURE_BINDING_ARRAY,
-                    features_webgpu: wgpu::FeaturesWebGPU::default(),
-                },
-                required_limits: wgpu::Limits {
-                    max_binding_array_elements_per_shader_stage: 4,
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .await
-            .unwrap();
+impl core::fmt::Display for ContextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextError::Surface(err) => write!(f, "failed to create the window surface: {err}"),
+            ContextError::Adapter(err) => write!(f, "no GPU adapter found: {err}"),
+            ContextError::Device(err) => write!(f, "failed to create a GPU device: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ContextError::Surface(err) => Some(err),
+            ContextError::Adapter(err) => Some(err),
+            ContextError::Device(err) => Some(err),
+        }
+    }
+}
+
+impl Context {
+    async fn new(window: Arc<winit::window::Window>) -> Result<Self, ContextError> {
+        let instance = Self::create_instance();
+        let surface = instance
+            .create_surface(window.clone())
+            .map_err(ContextError::Surface)?;
+        let (adapter, device, queue) = Self::request_device(&instance, Some(&surface)).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -78,185 +173,762 @@ impl Context {
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
         };
-        surface.configure(&device, &surface_configuration);
 
-        let renderer = renderer::Renderer::new(&device, &surface_configuration);
+        Ok(Self::with_device(
+            &adapter,
+            device,
+            queue,
+            surface_configuration,
+            Some(WindowSurface {
+                window,
+                surface,
+                present_modes: surface_caps.present_modes,
+            }),
+        ))
+    }
+
+    // a context without a window, which renders `width` x `height` frames of `format` with `read_frame`.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn new_headless(
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self, ContextError> {
+        let (adapter, device, queue) = Self::request_device(&Self::create_instance(), None).await?;
 
-        let scene = model::Scene::from_glb("res/scene2.glb").unwrap();
+        // only describes the frames, there's no surface to configure with it.
+        let surface_configuration = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
 
-        Self {
-            window,
-            surface,
+        Ok(Self::with_device(
+            &adapter,
+            device,
+            queue,
+            surface_configuration,
+            None,
+        ))
+    }
+
+    // `WGPU_BACKEND` picks the backends, e.g. `vulkan`, `dx12`, `metal` or `gl`.
+    fn create_instance() -> wgpu::Instance {
+        // the browser needs WebGPU, WebGL2 has no compute shaders or storage buffers for the lighting.
+        let backends = if cfg!(target_arch = "wasm32") {
+            wgpu::Backends::BROWSER_WEBGPU
+        } else {
+            wgpu::Backends::from_env().unwrap_or(wgpu::Backends::PRIMARY)
+        };
+        wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        })
+    }
+
+    // prefers a high performance adapter, or the one `WGPU_POWER_PREF` asks for (`high` or `low`),
+    // and falls back to wgpu's software adapter when there's none or it can't create a device.
+    async fn request_device(
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), ContextError> {
+        let attempt = async |power_preference, force_fallback_adapter| {
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference,
+                    force_fallback_adapter,
+                    compatible_surface,
+                })
+                .await
+                .map_err(ContextError::Adapter)?;
+            let (device, queue) = Self::request_adapter_device(&adapter)
+                .await
+                .map_err(ContextError::Device)?;
+            Ok((adapter, device, queue))
+        };
+
+        let power_preference =
+            wgpu::PowerPreference::from_env().unwrap_or(wgpu::PowerPreference::HighPerformance);
+        let result = match attempt(power_preference, false).await {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("{err}, trying the fallback adapter");
+                attempt(wgpu::PowerPreference::LowPower, true).await?
+            }
+        };
+        let info = result.0.get_info();
+        println!("adapter: {} ({})", info.name, info.backend);
+        Ok(result)
+    }
+
+    async fn request_adapter_device(
+        adapter: &wgpu::Adapter,
+    ) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+        // lets the renderer batch shadow pass draws into indirect calls, it falls back to a loop without it.
+        let indirect_first_instance = if adapter
+            .features()
+            .contains(wgpu::Features::INDIRECT_FIRST_INSTANCE)
+            && adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::INDIRECT_EXECUTION)
+        {
+            wgpu::FeaturesWebGPU::INDIRECT_FIRST_INSTANCE
+        } else {
+            wgpu::FeaturesWebGPU::default()
+        };
+
+        adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: wgpu::Features {
+                    features_wgpu: wgpu::FeaturesWGPU::empty(),
+                    features_webgpu: indirect_first_instance,
+                },
+                ..Default::default()
+            })
+            .await
+    }
+
+    // prints validation errors with wgpu's description, which names the pipeline, bind group or
+    // pass they happened in, instead of panicking on the first one. each is printed once, as
+    // errors in a frame's commands repeat every frame. running out of memory still panics.
+    fn report_device_errors(device: &wgpu::Device) {
+        let reported = std::sync::Mutex::new(std::collections::HashSet::new());
+        device.on_uncaptured_error(Arc::new(move |err| {
+            if let wgpu::Error::OutOfMemory { .. } = err {
+                panic!("wgpu error: {err}");
+            }
+            let message = err.to_string();
+            if reported.lock().unwrap().insert(message.clone()) {
+                eprintln!("wgpu error: {message}");
+            }
+        }));
+    }
+
+    // everything but the window and the device: the renderer and scene.
+    fn with_device(
+        adapter: &wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface_configuration: wgpu::SurfaceConfiguration,
+        window_surface: Option<WindowSurface>,
+    ) -> Self {
+        Self::report_device_errors(&device);
+        if let Some(WindowSurface { surface, .. }) = &window_surface {
+            surface.configure(&device, &surface_configuration);
+        }
+
+        // fall back to no MSAA when the adapter can't multisample the render targets.
+        let msaa_supported = [renderer::HDR_FORMAT, wgpu::TextureFormat::Depth32Float]
+            .iter()
+            .all(|format| {
+                adapter
+                    .get_texture_format_features(*format)
+                    .flags
+                    .sample_count_supported(SAMPLE_COUNT)
+            });
+        let sample_count = if msaa_supported { SAMPLE_COUNT } else { 1 };
+
+        let mut renderer =
+            renderer::Renderer::new(&device, &queue, &surface_configuration, sample_count);
+        if !adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING)
+        {
+            renderer.anisotropy_clamp = 1;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let scene_watcher = file_watcher::FileWatcher::new(SCENE_PATH)
+            .inspect_err(|err| eprintln!("not watching {SCENE_PATH} for changes: {err}"))
+            .ok();
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        let shader_watcher = file_watcher::FileWatcher::new(SHADER_PATH)
+            .inspect_err(|err| eprintln!("not watching {SHADER_PATH} for changes: {err}"))
+            .ok();
+
+        // the environment is optional, without it the background is the clear color.
+        let environment_dir = "res/environment";
+        if std::path::Path::new(environment_dir).is_dir() {
+            let faces = model::Image::load_cube_map(environment_dir)
+                .unwrap_or_else(|err| panic!("failed to load {environment_dir}: {err}"));
+            renderer.set_environment(&device, &queue, Some(&faces));
+        }
+
+        let text_overlay = text::TextOverlay::new(&device, &queue, surface_configuration.format);
+
+        let context = Self {
+            window_surface,
             device,
             queue,
             surface_configuration,
             renderer,
-            scene,
+            text_overlay,
+            scene: model::Scene::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            scene_loader: Some(scene_loader::SceneLoader::new(SCENE_PATH, false)),
+            #[cfg(not(target_arch = "wasm32"))]
+            scene_watcher,
+            #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+            shader_watcher,
             cursor_visible: true,
+            cursor_position: None,
             focused: true,
             frame_instant: Instant::now(),
-            pressed_key: HashSet::new(),
-            mouse_motion: (0.0, 0.0),
+            key_bindings: key_bindings::KeyBindings::default(),
+            camera_controller: camera_controller::CameraController::default(),
+            camera_path: camera_path::CameraPath::default(),
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::Gamepad::new(),
             time: 0,
-        }
-    }
-
-    fn is_key_pressed(&mut self, code: KeyCode) -> bool {
-        self.pressed_key.contains(&code)
+            frame_times: VecDeque::with_capacity(FRAME_TIME_FRAMES),
+            show_frame_time: false,
+            fit_clip_planes: true,
+            culled_draws: 0,
+            pending_size: None,
+            minimized: false,
+        };
+        // there's no filesystem or threads in the browser, the scene is embedded instead.
+        #[cfg(target_arch = "wasm32")]
+        let context = {
+            let mut context = context;
+            context.finish_loading(
+                false,
+                model::Scene::from_glb_bytes(include_bytes!("../res/scene2.glb")),
+            );
+            context
+        };
+        context.update_title();
+        context
     }
 
     fn update(&mut self) {
         let now = Instant::now();
         let dt = now - self.frame_instant;
-        let dt_sec = dt.as_secs_f32();
         self.frame_instant = now;
 
-        self.time += dt.as_nanos() as u64;
-
-        let camera_speed = 10.0;
-        let forward_dir = self.scene.camera.forward_vec();
-
-        let right_dir = forward_dir.cross(Vec3::Y);
+        if self.frame_times.len() == FRAME_TIME_FRAMES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(dt);
 
-        let mut dir = Vec3::ZERO;
-        if self.is_key_pressed(KeyCode::KeyW) {
-            dir += forward_dir;
+        #[cfg(not(target_arch = "wasm32"))]
+        if self
+            .scene_watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.changed())
+        {
+            self.reload_scene();
         }
-        if self.is_key_pressed(KeyCode::KeyS) {
-            dir -= forward_dir;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(loader) = &self.scene_loader
+            && let Some(result) = loader.poll()
+        {
+            let reload = loader.reload;
+            self.scene_loader = None;
+            self.finish_loading(reload, result);
         }
-        if self.is_key_pressed(KeyCode::KeyA) {
-            dir -= right_dir;
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        if self
+            .shader_watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.changed())
+        {
+            self.reload_shader();
         }
-        if self.is_key_pressed(KeyCode::KeyD) {
-            dir += right_dir;
+
+        self.time += dt.as_nanos() as u64;
+        self.scene
+            .update(Duration::from_nanos(self.time).as_secs_f32());
+
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepad) = &mut self.gamepad {
+            gamepad.poll(&mut self.camera_controller);
         }
-        if self.is_key_pressed(KeyCode::Space) {
-            dir += Vec3::Y;
+        // the flythrough has the camera to itself while it plays.
+        if self.camera_path.is_playing() {
+            self.camera_path
+                .update(&mut self.scene.camera, dt.as_secs_f32());
+        } else {
+            self.camera_controller
+                .update(&mut self.scene.camera, dt.as_secs_f32());
         }
-        if self.is_key_pressed(KeyCode::ShiftLeft) {
-            dir += Vec3::NEG_Y;
+        if self.fit_clip_planes
+            && let Some(bounds) = self.scene.bounds()
+        {
+            self.scene.camera.fit_clip_planes(bounds);
         }
+    }
 
-        self.scene.camera.position += dir.normalize_or_zero() * camera_speed * dt_sec;
+    // turns the sun by `azimuth` and raises it by `elevation`, in radians, and scales its flux by
+    // `flux_factor`. does nothing while it's off.
+    fn adjust_sun(&mut self, azimuth: f32, elevation: f32, flux_factor: f32) {
+        let Some(sun) = &mut self.scene.sun else {
+            return;
+        };
+        sun.azimuth = (sun.azimuth + azimuth).rem_euclid(std::f32::consts::TAU);
+        sun.elevation = (sun.elevation + elevation)
+            .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2);
+        sun.radiant_flux *= flux_factor;
+        println!(
+            "sun: {:.0} degrees azimuth, {:.0} degrees elevation, flux {}",
+            sun.azimuth.to_degrees(),
+            sun.elevation.to_degrees(),
+            sun.radiant_flux
+        );
+    }
 
-        let sensitivity = 0.002;
-        self.scene.camera.yaw -= sensitivity * self.mouse_motion.0 as f32;
-        self.scene.camera.pitch -= sensitivity * self.mouse_motion.1 as f32;
+    // scales the near or far plane by `factor` and keeps it there. an infinite far plane starts
+    // from where fitting would put it.
+    fn move_clip_plane(&mut self, far: bool, factor: f32) {
+        self.fit_clip_planes = false;
+        let bounds = self.scene.bounds();
+        let camera = &mut self.scene.camera;
+        if far {
+            let zfar = camera.zfar.or_else(|| {
+                let mut fitted = camera.clone();
+                fitted.fit_clip_planes(bounds?);
+                fitted.zfar
+            });
+            camera.zfar = zfar.map(|zfar| (zfar * factor).max(2.0 * camera.znear));
+        } else {
+            let znear = camera.znear * factor;
+            camera.znear = camera.zfar.map_or(znear, |zfar| znear.min(0.5 * zfar));
+        }
+        println!("clip planes: {} to {:?}", camera.znear, camera.zfar);
+    }
 
-        self.mouse_motion = (0.0, 0.0);
+    // loads `SCENE_PATH` again in the background, to be seen from the current camera.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_scene(&mut self) {
+        // reloading before the first load finished is still the first load.
+        let reload = self
+            .scene_loader
+            .as_ref()
+            .is_none_or(|loader| loader.reload);
+        self.scene_loader = Some(scene_loader::SceneLoader::new(SCENE_PATH, reload));
+        self.update_title();
+    }
 
-        let sensitivity = 1.0;
-        if self.is_key_pressed(KeyCode::ArrowLeft) {
-            self.scene.camera.yaw += sensitivity * dt_sec;
+    // blocks until the scene being loaded is shown.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn wait_for_scene(&mut self) {
+        if let Some(loader) = self.scene_loader.take() {
+            let reload = loader.reload;
+            self.finish_loading(reload, loader.wait());
         }
-        if self.is_key_pressed(KeyCode::ArrowRight) {
-            self.scene.camera.yaw -= sensitivity * dt_sec;
-        }
-        if self.is_key_pressed(KeyCode::ArrowUp) {
-            self.scene.camera.pitch += sensitivity * dt_sec;
+    }
+
+    // uploads a loaded scene's textures and shows it. a reload keeps the camera and the sun, and
+    // keeps the current scene if it fails, e.g. while the file is still being written.
+    fn finish_loading(&mut self, reload: bool, result: Result<model::Scene, model::SceneError>) {
+        let mut scene = match result {
+            Ok(scene) => scene,
+            Err(err) if reload => {
+                eprintln!("failed to reload {SCENE_PATH}: {err}");
+                self.update_title();
+                return;
+            }
+            Err(err) => panic!("failed to load {SCENE_PATH}: {err}"),
+        };
+        if reload {
+            scene.camera = self.scene.camera.clone();
+            scene.sun = self.scene.sun.clone();
+        } else if scene.lights.is_empty() {
+            // scenes without lights would be black, the sun lights them until it's turned off.
+            scene.sun = Some(model::Sun::default());
         }
-        if self.is_key_pressed(KeyCode::ArrowDown) {
-            self.scene.camera.pitch -= sensitivity * dt_sec;
+        self.renderer
+            .upload_textures(&self.device, &self.queue, &scene);
+        // the reloaded file may have its meshes in another order.
+        self.renderer.set_highlight(None);
+        self.scene = scene;
+        if reload {
+            println!("reloaded {SCENE_PATH}");
         }
-        self.scene.camera.pitch = f32::clamp(
-            self.scene.camera.pitch,
-            -std::f32::consts::PI * 0.5,
-            std::f32::consts::PI * 0.5,
-        );
+        self.update_title();
+    }
 
-        if self.is_key_pressed(KeyCode::Minus) {
-            self.scene.camera.yfov += 0.5 * dt_sec
+    // shows whether the scene is loading, or else how many draws were culled.
+    fn update_title(&self) {
+        let Some(window) = self.window() else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.scene_loader.is_some() {
+            window.set_title(&format!(
+                "Physically based rendering (loading {SCENE_PATH})"
+            ));
+            return;
         }
-        if self.is_key_pressed(KeyCode::Equal) {
-            self.scene.camera.yfov -= 0.5 * dt_sec
+        window.set_title(&format!(
+            "Physically based rendering ({} draws culled)",
+            self.culled_draws
+        ));
+    }
+
+    // rebuilds the renderer's pipelines from `SHADER_PATH`. errors are reported and the
+    // previous shader stays in use.
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    fn reload_shader(&mut self) {
+        let result = std::fs::read_to_string(SHADER_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|source| {
+                pollster::block_on(self.renderer.reload_shader(&self.device, &source))
+                    .map_err(|err| err.to_string())
+            });
+        match result {
+            Ok(()) => println!("reloaded {SHADER_PATH}"),
+            Err(err) => eprintln!("failed to reload {SHADER_PATH}: {err}"),
         }
-        self.scene.camera.yfov = f32::clamp(self.scene.camera.yfov, 0.01, std::f32::consts::PI);
     }
 
+    // over the latest `FRAME_TIME_FRAMES` frames. None before the first frame.
+    fn average_frame_time(&self) -> Option<Duration> {
+        let frames = self.frame_times.len() as u32;
+        (frames > 0).then(|| self.frame_times.iter().sum::<Duration>() / frames)
+    }
+
+    // reconfigures the surface with `present_mode`, or with Fifo if the surface doesn't support it.
+    // returns the mode in use.
+    fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        let Some(window_surface) = &self.window_surface else {
+            return self.surface_configuration.present_mode;
+        };
+        // every surface supports Fifo.
+        self.surface_configuration.present_mode =
+            if window_surface.present_modes.contains(&present_mode) {
+                present_mode
+            } else {
+                wgpu::PresentMode::Fifo
+            };
+        window_surface
+            .surface
+            .configure(&self.device, &self.surface_configuration);
+        self.surface_configuration.present_mode
+    }
+
+    // the supported mode after the current one, cycling through vsync, mailbox and immediate.
+    fn next_present_mode(&self) -> wgpu::PresentMode {
+        const CYCLE: [wgpu::PresentMode; 3] = [
+            wgpu::PresentMode::Fifo,
+            wgpu::PresentMode::Mailbox,
+            wgpu::PresentMode::Immediate,
+        ];
+        let Some(window_surface) = &self.window_surface else {
+            return self.surface_configuration.present_mode;
+        };
+        let current = CYCLE
+            .iter()
+            .position(|mode| *mode == self.surface_configuration.present_mode)
+            .unwrap_or(0);
+        (1..=CYCLE.len())
+            .map(|offset| CYCLE[(current + offset) % CYCLE.len()])
+            .find(|mode| window_surface.present_modes.contains(mode))
+            .unwrap_or(wgpu::PresentMode::Fifo)
+    }
+
+    fn window(&self) -> Option<&winit::window::Window> {
+        self.window_surface
+            .as_ref()
+            .map(|window_surface| window_surface.window.as_ref())
+    }
+
+    // renders a frame to the window. does nothing for headless contexts.
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let Some(WindowSurface { surface, .. }) = &self.window_surface else {
+            return Ok(());
+        };
+        if self.minimized {
+            return Ok(());
+        }
+        let output = surface.get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut command_encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.render_to(&view);
 
-        self.renderer
-            .write_vertex(&self.device, &self.queue, &self.scene);
-        self.renderer
-            .render(&mut command_encoder, &view, &self.queue, &self.scene);
+        if let Some(window) = self.window() {
+            window.pre_present_notify();
+        }
+        output.present();
 
-        self.queue.submit(std::iter::once(command_encoder.finish()));
+        Ok(())
+    }
 
-        self.window.pre_present_notify();
-        output.present();
+    // renders a frame into an offscreen texture and returns its RGBA8 pixels, row by row.
+    // the browser can't block on the readback.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_frame(&mut self) -> Result<Vec<u8>, texture::ReadbackError> {
+        let target = texture::Texture::create_readback_target(
+            &self.device,
+            self.surface_configuration.width,
+            self.surface_configuration.height,
+            self.surface_configuration.format,
+            Some("capture"),
+        );
+        self.render_to(&target.view);
+        target.read_rgba8(&self.device, &self.queue)
+    }
 
+    // renders a frame into an offscreen texture and saves it to `path` as a PNG.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn capture_frame(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let pixels = self.read_frame()?;
+        image::save_buffer(
+            path,
+            &pixels,
+            self.surface_configuration.width,
+            self.surface_configuration.height,
+            image::ExtendedColorType::Rgba8,
+        )?;
         Ok(())
     }
 
+    // `view` must have the surface's format and size.
+    fn render_to(&mut self, view: &wgpu::TextureView) {
+        self.renderer
+            .render_to(&self.device, &self.queue, &self.scene, view);
+        if self.renderer.culled_draws != self.culled_draws {
+            self.culled_draws = self.renderer.culled_draws;
+            self.update_title();
+        }
+        if self.show_frame_time
+            && let Some(frame_time) = self.average_frame_time()
+        {
+            let mut command_encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            let ms = frame_time.as_secs_f64() * 1000.0;
+            self.text_overlay.render(
+                &mut command_encoder,
+                &self.queue,
+                view,
+                self.surface_configuration.width,
+                self.surface_configuration.height,
+                &format!(
+                    "{:.0} FPS {ms:.2} ms {:+.1} EV",
+                    1000.0 / ms,
+                    self.renderer.exposure_ev()
+                ),
+            );
+            self.queue.submit(std::iter::once(command_encoder.finish()));
+        }
+    }
+
     fn add_mouse_motion(&mut self, delta: (f64, f64)) {
-        if !self.cursor_visible {
-            self.mouse_motion.0 += delta.0;
-            self.mouse_motion.1 += delta.1;
+        // orbiting turns with drags of the visible cursor.
+        if !self.cursor_visible || self.camera_controller.is_orbiting() {
+            self.camera_controller.process_mouse(delta);
         }
     }
 
     fn resize(&mut self, width: u32, height: u32) {
+        self.minimized = width == 0 || height == 0;
+        if self.minimized {
+            return;
+        }
         self.surface_configuration.width = width;
         self.surface_configuration.height = height;
-        self.surface
-            .configure(&self.device, &self.surface_configuration);
+        if let Some(WindowSurface { surface, .. }) = &self.window_surface {
+            surface.configure(&self.device, &self.surface_configuration);
+        }
 
         self.renderer.resize(&self.device, width, height);
 
-        self.window.request_redraw();
+        if let Some(window) = self.window() {
+            window.request_redraw();
+        }
+    }
+
+    // resizes to the pending size, if it differs from the current one or restores a minimized window.
+    fn apply_pending_resize(&mut self) {
+        if let Some(size) = self.pending_size.take()
+            && (self.minimized
+                || (size.width, size.height)
+                    != (
+                        self.surface_configuration.width,
+                        self.surface_configuration.height,
+                    ))
+        {
+            self.resize(size.width, size.height);
+        }
+    }
+
+    // replaces the camera with the one saved in `CAMERA_PATH`, and flies from there.
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    fn load_camera(&mut self) {
+        match model::Camera::load(CAMERA_PATH) {
+            Ok(camera) => {
+                self.scene.camera = camera;
+                self.stop_orbiting();
+                println!("loaded {CAMERA_PATH}");
+            }
+            Err(err) => eprintln!("failed to load {CAMERA_PATH}: {err}"),
+        }
+    }
+
+    // plays the recorded flythrough from the start, or stops it.
+    fn toggle_flythrough(&mut self) {
+        if self.camera_path.is_playing() {
+            self.camera_path.stop();
+            return;
+        }
+        self.camera_path.play();
+        if self.camera_path.is_playing() {
+            self.stop_orbiting();
+        } else {
+            println!("a flythrough needs at least 2 keyframes");
+        }
+    }
+
+    // flies from wherever the camera is. orbiting would move it back around the pivot.
+    fn stop_orbiting(&mut self) {
+        if self.camera_controller.is_orbiting() {
+            self.camera_controller.fly();
+            self.set_cursor_visible(!self.focused);
+        }
+    }
+
+    // orbits around the center of the scene, or flies again.
+    fn toggle_orbit(&mut self) {
+        if self.camera_controller.is_orbiting() {
+            self.camera_controller.fly();
+            self.set_cursor_visible(!self.focused);
+        } else {
+            let camera = &self.scene.camera;
+            let pivot = match self.scene.bounds() {
+                Some(bounds) => bounds.center(),
+                None => camera.position + camera.direction(),
+            };
+            self.camera_controller.orbit(&mut self.scene.camera, pivot);
+            self.set_cursor_visible(true);
+        }
+    }
+
+    // outlines the mesh under the cursor, or in the middle of the window while the cursor is
+    // hidden, and prints where it was hit. picking nothing clears the outline.
+    fn pick(&mut self) {
+        let width = self.surface_configuration.width as f32;
+        let height = self.surface_configuration.height as f32;
+        let (x, y) = match self.cursor_position {
+            Some(position) if self.cursor_visible => (position.x as f32, position.y as f32),
+            _ => (width / 2.0, height / 2.0),
+        };
+        let ndc = glam::Vec2::new(x / width * 2.0 - 1.0, 1.0 - y / height * 2.0);
+        let (origin, direction) = self.scene.camera.ray(width / height, ndc);
+        let hit = self.scene.raycast(origin, direction);
+        match hit {
+            Some((mesh, distance)) => println!(
+                "picked {} at {:.3}, {distance:.3} away",
+                self.scene.meshes[mesh]
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("mesh {mesh}")),
+                origin + direction * distance
+            ),
+            None => println!("nothing picked"),
+        }
+        self.renderer.set_highlight(hit.map(|(mesh, _)| mesh));
     }
 
     fn set_cursor_visible(&mut self, visible: bool) {
         self.cursor_visible = visible;
-        self.window.set_cursor_visible(visible);
+        let Some(window) = self.window() else {
+            return;
+        };
+        window.set_cursor_visible(visible);
         if visible {
-            self.window
+            window
                 .set_cursor_grab(winit::window::CursorGrabMode::None)
                 .unwrap();
         } else {
-            let window_size = self.window.inner_size();
-            self.window
+            let window_size = window.inner_size();
+            window
                 .set_cursor_grab(winit::window::CursorGrabMode::None)
                 .unwrap();
-            self.window
+            window
                 .set_cursor_position(winit::dpi::PhysicalPosition::new(
                     window_size.width / 2,
                     window_size.height / 2,
                 ))
                 .unwrap();
-            self.window
+            window
                 .set_cursor_grab(winit::window::CursorGrabMode::Locked)
                 .unwrap();
         }
     }
 }
 
-#[derive(Default)]
 struct App {
     context: Option<Context>,
+    // the browser can't block on creating the context, it's sent back as a user event instead.
+    #[cfg(target_arch = "wasm32")]
+    proxy: winit::event_loop::EventLoopProxy<Context>,
 }
 
-impl winit::application::ApplicationHandler for App {
+impl App {
+    fn new(_event_loop: &winit::event_loop::EventLoop<Context>) -> Self {
+        Self {
+            context: None,
+            #[cfg(target_arch = "wasm32")]
+            proxy: _event_loop.create_proxy(),
+        }
+    }
+}
+
+impl winit::application::ApplicationHandler<Context> for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         let icon = winit::window::Icon::from_rgba(include_bytes!("icon").into(), 32, 32).unwrap();
 
-        let window = event_loop
-            .create_window(
-                winit::window::WindowAttributes::default()
-                    .with_title("Physically based rendering")
-                    .with_window_icon(Some(icon)),
-            )
-            .unwrap();
+        let window_attributes = winit::window::WindowAttributes::default()
+            .with_title("Physically based rendering")
+            .with_window_icon(Some(icon));
+        // in the browser, the window is a canvas appended to the page.
+        #[cfg(target_arch = "wasm32")]
+        let window_attributes = {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            window_attributes
+                .with_inner_size(winit::dpi::PhysicalSize::new(
+                    HEADLESS_WIDTH,
+                    HEADLESS_HEIGHT,
+                ))
+                .with_append(true)
+        };
+        let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
 
-        self.context = Some(pollster::block_on(Context::new(Arc::new(window))));
+        #[cfg(not(target_arch = "wasm32"))]
+        match pollster::block_on(Context::new(window)) {
+            Ok(context) => self.context = Some(context),
+            Err(err) => {
+                eprintln!("{err}");
+                event_loop.exit();
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let proxy = self.proxy.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let context = Context::new(window)
+                    .await
+                    .unwrap_or_else(|err| panic!("{err}"));
+                // only fails once the page is closed.
+                let _ = proxy.send_event(context);
+            });
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop, context: Context) {
+        if let Some(window) = context.window() {
+            window.request_redraw();
+        }
+        self.context = Some(context);
     }
 
     fn device_event(
@@ -288,17 +960,29 @@ impl winit::application::ApplicationHandler for App {
 
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => {
+                context.pending_size = Some(size);
+                // redraws stop while minimized, restoring the window starts them again.
+                if let Some(window) = context.window() {
+                    window.request_redraw();
+                }
+            }
             WindowEvent::RedrawRequested => {
+                context.apply_pending_resize();
                 context.update();
                 // redraw
                 if let Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) =
                     context.render()
+                    && let Some(size) = context.window().map(|window| window.inner_size())
                 {
-                    let size = context.window.inner_size();
                     context.resize(size.width, size.height);
                 }
 
-                context.window.request_redraw();
+                if !context.minimized
+                    && let Some(window) = context.window()
+                {
+                    window.request_redraw();
+                }
             }
             WindowEvent::KeyboardInput { event, .. } => match event {
                 KeyEvent {
@@ -308,40 +992,424 @@ impl winit::application::ApplicationHandler for App {
                 } => event_loop.exit(),
 
                 KeyEvent {
-                    physical_key: PhysicalKey::Code(KeyCode::AltLeft),
-                    state,
+                    physical_key: PhysicalKey::Code(KeyCode::F1),
+                    state: ElementState::Pressed,
+                    repeat: false,
                     ..
-                } => match state {
-                    ElementState::Pressed => {
-                        context.set_cursor_visible(true);
-                    }
-                    ElementState::Released => {
-                        context.set_cursor_visible(false);
+                } => {
+                    context.renderer.show_shadow_maps = !context.renderer.show_shadow_maps;
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F2),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    let camera = &mut context.scene.camera;
+                    camera.mode = match camera.mode {
+                        CameraMode::Normal => CameraMode::TopDown,
+                        CameraMode::TopDown => CameraMode::Normal,
+                    };
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F3),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    context.renderer.tone_mapping = match context.renderer.tone_mapping {
+                        ToneMapping::Reinhard => ToneMapping::Aces,
+                        ToneMapping::Aces => ToneMapping::Reinhard,
+                    };
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F4),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    context.renderer.fxaa_enabled = !context.renderer.fxaa_enabled;
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F5),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    let present_mode = context.set_present_mode(context.next_present_mode());
+                    println!("present mode: {present_mode:?}");
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F6),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    context.show_frame_time = !context.show_frame_time;
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyG),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    let show = !context.renderer.is_grid_shown();
+                    context.renderer.show_grid(show);
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyM),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    // a white dielectric, to see the lighting without the scene's materials.
+                    let material = match context.renderer.material_override() {
+                        Some(_) => None,
+                        None => Some(model::Material {
+                            metallic: 0.0,
+                            roughness: 0.5,
+                            ..Default::default()
+                        }),
+                    };
+                    context.renderer.set_material_override(material);
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F7),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    context.renderer.show_light_gizmos = !context.renderer.show_light_gizmos;
+                }
+
+                KeyEvent {
+                    physical_key:
+                        PhysicalKey::Code(code @ (KeyCode::BracketLeft | KeyCode::BracketRight)),
+                    state: ElementState::Pressed,
+                    ..
+                } => {
+                    let step = match code {
+                        KeyCode::BracketLeft => -EXPOSURE_STEP,
+                        _ => EXPOSURE_STEP,
+                    };
+                    let ev = context.renderer.exposure_ev() + step;
+                    context.renderer.set_exposure_ev(ev);
+                    println!("exposure: {ev:+.1} EV");
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(code),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } if DEBUG_VIEW_KEYS.contains(&code) => {
+                    let index = DEBUG_VIEW_KEYS.iter().position(|key| *key == code).unwrap();
+                    context.renderer.debug_view = DebugView::ALL[index];
+                    println!("debug view: {:?}", context.renderer.debug_view);
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyR),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => context.reload_scene(),
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyN),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    let time = Duration::from_nanos(context.time).as_secs_f32();
+                    context
+                        .camera_path
+                        .add_keyframe(&context.scene.camera, time);
+                    println!("flythrough keyframes: {}", context.camera_path.len());
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyP),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => context.toggle_flythrough(),
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::Delete),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => context.camera_path.clear(),
+
+                #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F10),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => match context.camera_path.save(FLYTHROUGH_PATH) {
+                    Ok(()) => println!("saved {FLYTHROUGH_PATH}"),
+                    Err(err) => eprintln!("failed to save {FLYTHROUGH_PATH}: {err}"),
+                },
+
+                #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F11),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => match camera_path::CameraPath::load(FLYTHROUGH_PATH) {
+                    Ok(path) => {
+                        context.camera_path = path;
+                        println!("loaded {FLYTHROUGH_PATH}");
                     }
+                    Err(err) => eprintln!("failed to load {FLYTHROUGH_PATH}: {err}"),
                 },
+
+                #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
                 KeyEvent {
-                    physical_key: PhysicalKey::Code(code),
-                    state,
+                    physical_key: PhysicalKey::Code(KeyCode::F8),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => match context.scene.camera.save(CAMERA_PATH) {
+                    Ok(()) => println!("saved {CAMERA_PATH}"),
+                    Err(err) => eprintln!("failed to save {CAMERA_PATH}: {err}"),
+                },
+
+                #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F9),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => context.load_camera(),
+
+                // there's nowhere to save it in the browser.
+                #[cfg(not(target_arch = "wasm32"))]
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::F12),
+                    state: ElementState::Pressed,
+                    repeat: false,
                     ..
                 } => {
-                    if state.is_pressed() {
-                        context.pressed_key.insert(code);
-                    } else {
-                        context.pressed_key.remove(&code);
+                    let timestamp = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    let path = format!("screenshot-{timestamp}.png");
+                    match context.capture_frame(&path) {
+                        Ok(()) => println!("saved {path}"),
+                        Err(err) => eprintln!("failed to save {path}: {err}"),
                     }
                 }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyF),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    let controller = &mut context.camera_controller;
+                    controller.free_flight = !controller.free_flight;
+                }
+
+                KeyEvent {
+                    physical_key:
+                        PhysicalKey::Code(
+                            code @ (KeyCode::Comma
+                            | KeyCode::Period
+                            | KeyCode::Semicolon
+                            | KeyCode::Quote),
+                        ),
+                    state: ElementState::Pressed,
+                    ..
+                } => {
+                    let far = matches!(code, KeyCode::Semicolon | KeyCode::Quote);
+                    let factor = match code {
+                        KeyCode::Comma | KeyCode::Semicolon => 0.5,
+                        _ => 2.0,
+                    };
+                    context.move_clip_plane(far, factor);
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyT),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    context.scene.sun = match context.scene.sun {
+                        Some(_) => None,
+                        None => Some(model::Sun::default()),
+                    };
+                }
+
+                KeyEvent {
+                    physical_key:
+                        PhysicalKey::Code(
+                            code @ (KeyCode::Home
+                            | KeyCode::End
+                            | KeyCode::PageUp
+                            | KeyCode::PageDown
+                            | KeyCode::KeyY
+                            | KeyCode::KeyH),
+                        ),
+                    state: ElementState::Pressed,
+                    ..
+                } => {
+                    let (azimuth, elevation, flux_factor) = match code {
+                        KeyCode::Home => (-SUN_ANGLE_STEP, 0.0, 1.0),
+                        KeyCode::End => (SUN_ANGLE_STEP, 0.0, 1.0),
+                        KeyCode::PageUp => (0.0, SUN_ANGLE_STEP, 1.0),
+                        KeyCode::PageDown => (0.0, -SUN_ANGLE_STEP, 1.0),
+                        KeyCode::KeyY => (0.0, 0.0, SUN_FLUX_STEP),
+                        _ => (0.0, 0.0, 1.0 / SUN_FLUX_STEP),
+                    };
+                    context.adjust_sun(azimuth, elevation, flux_factor);
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::Backslash),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => {
+                    context.fit_clip_planes = true;
+                    println!("fitting the clip planes to the scene");
+                }
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(KeyCode::KeyC),
+                    state: ElementState::Pressed,
+                    repeat: false,
+                    ..
+                } => context.toggle_orbit(),
+
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(code),
+                    state,
+                    ..
+                } => match context.key_bindings.action(code) {
+                    Some(key_bindings::Action::ToggleCursor) => {
+                        let orbiting = context.camera_controller.is_orbiting();
+                        context.set_cursor_visible(state.is_pressed() || orbiting);
+                    }
+                    Some(action) => context.camera_controller.process_keyboard(action, state),
+                    None => (),
+                },
                 _ => (),
             },
+            WindowEvent::MouseInput { state, button, .. } => {
+                if button == winit::event::MouseButton::Right && state.is_pressed() {
+                    context.pick();
+                }
+                context
+                    .camera_controller
+                    .process_mouse_button(button, state);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                context.cursor_position = Some(position);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                context.camera_controller.process_scroll(delta);
+            }
             WindowEvent::Focused(focus) => {
                 context.focused = focus;
-                context.set_cursor_visible(!focus);
+                let orbiting = context.camera_controller.is_orbiting();
+                context.set_cursor_visible(!focus || orbiting);
             }
             _ => (),
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    // `--headless <path>` saves a single frame without opening a window.
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, flag, path] = args.as_slice()
+        && flag == "--headless"
+    {
+        let mut context = pollster::block_on(Context::new_headless(
+            HEADLESS_WIDTH,
+            HEADLESS_HEIGHT,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        ))
+        .unwrap_or_else(|err| panic!("{err}"));
+        context.wait_for_scene();
+        context.scene.update(0.0);
+        context
+            .capture_frame(path)
+            .unwrap_or_else(|err| panic!("failed to save {path}: {err}"));
+        return;
+    }
+
+    let event_loop = winit::event_loop::EventLoop::with_user_event()
+        .build()
+        .unwrap();
+    let mut app = App::new(&event_loop);
+    event_loop.run_app(&mut app).unwrap();
+}
+
+#[cfg(target_arch = "wasm32")]
 fn main() {
-    let event_loop = winit::event_loop::EventLoop::new().unwrap();
-    event_loop.run_app(&mut App::default()).unwrap();
+    use winit::platform::web::EventLoopExtWebSys;
+
+    let event_loop = winit::event_loop::EventLoop::with_user_event()
+        .build()
+        .unwrap();
+    let app = App::new(&event_loop);
+    // returns right away, the browser drives the event loop.
+    event_loop.spawn_app(app);
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    // what a window being minimized and restored goes through, skipped without an adapter.
+    #[test]
+    fn minimized_resize() {
+        let mut context = match pollster::block_on(Context::new_headless(
+            64,
+            48,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        )) {
+            Ok(context) => context,
+            Err(err) => {
+                eprintln!("skipped, {err}");
+                return;
+            }
+        };
+
+        context.pending_size = Some(winit::dpi::PhysicalSize::new(0, 0));
+        context.apply_pending_resize();
+        context.update();
+        context.render().unwrap();
+        assert!(context.minimized);
+        // the renderer keeps its targets at the last real size.
+        assert_eq!(
+            (
+                context.surface_configuration.width,
+                context.surface_configuration.height
+            ),
+            (64, 48)
+        );
+
+        context.pending_size = Some(winit::dpi::PhysicalSize::new(32, 24));
+        context.apply_pending_resize();
+        assert!(!context.minimized);
+        assert_eq!(context.read_frame().unwrap().len(), 4 * 32 * 24);
+    }
 }
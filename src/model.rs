@@ -1,6 +1,9 @@
-use glam::{Mat3, Mat4, Quat, Vec3, Vec4};
+use glam::{Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
 
-#[derive(Clone, Debug)]
+pub mod shapes;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transform {
     pub translation: Vec3,
     pub rotation: Quat,
@@ -15,6 +18,105 @@ impl Transform {
     pub fn rot(&self) -> Mat3 {
         Mat3::from_quat(self.rotation) * Mat3::from_diagonal(self.scale.recip())
     }
+
+    // `self` at `t` = 0 and `other` at 1. the rotation turns along the shortest arc.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+// axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    // contains nothing, so it's the starting point for `union`.
+    pub const EMPTY: Self = Self {
+        min: Vec3::INFINITY,
+        max: Vec3::NEG_INFINITY,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    // the box around this one's corners after `matrix`, looser than a fresh fit when rotated.
+    pub fn transform(self, matrix: Mat4) -> Self {
+        if self.is_empty() {
+            return self;
+        }
+        (0..8).fold(Self::EMPTY, |aabb, i| {
+            let corner = Vec3::select(
+                glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+                self.max,
+                self.min,
+            );
+            let corner = matrix.transform_point3(corner);
+            aabb.union(Self {
+                min: corner,
+                max: corner,
+            })
+        })
+    }
+
+    // distance along `direction`, in units of its length, to where the ray from `origin` enters
+    // the box. 0 when it starts inside.
+    pub fn ray_intersection(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        // slab test. zero components of `direction` divide into infinities, which compare right.
+        let inverse = direction.recip();
+        let t0 = (self.min - origin) * inverse;
+        let t1 = (self.max - origin) * inverse;
+        let enter = t0.min(t1).max_element().max(0.0);
+        let exit = t0.max(t1).min_element();
+        (enter <= exit).then_some(enter)
+    }
+}
+
+// distance along `direction` to where the ray from `origin` crosses the triangle, from either side.
+// Möller-Trumbore.
+fn ray_triangle_intersection(origin: Vec3, direction: Vec3, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = direction.cross(ac);
+    let det = ab.dot(p);
+    // parallel to the triangle, or a degenerate one.
+    if det == 0.0 {
+        return None;
+    }
+    let ao = origin - a;
+    let u = ao.dot(p) / det;
+    let q = ao.cross(ab);
+    let v = direction.dot(q) / det;
+    if u < 0.0 || v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(q) / det;
+    (t >= 0.0).then_some(t)
 }
 
 impl core::fmt::Display for Transform {
@@ -26,6 +128,18 @@ impl core::fmt::Display for Transform {
     }
 }
 
+// shear from non-uniformly scaled parents of rotated nodes can't be represented and is dropped.
+impl From<Mat4> for Transform {
+    fn from(value: Mat4) -> Self {
+        let (scale, rotation, translation) = value.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+}
+
 impl From<gltf::scene::Transform> for Transform {
     fn from(value: gltf::scene::Transform) -> Self {
         let decomposed = value.decomposed();
@@ -38,66 +152,968 @@ impl From<gltf::scene::Transform> for Transform {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Primitive {
     pub vertices: Vec<crate::renderer::Vertex>,
     pub indices: Vec<u32>,
+    pub topology: Topology,
     pub material: Material,
+    pub base_color_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    // roughness in green and metallic in blue, scaling the material's factors.
+    pub metallic_roughness_texture: Option<TextureRef>,
+    // baked ambient occlusion in red, 1 where unoccluded.
+    pub occlusion_texture: Option<TextureRef>,
+    // emitted color, scaling the material's emissive factor.
+    pub emissive_texture: Option<TextureRef>,
+    // KHR_materials_clearcoat's own tangent space normal map.
+    pub clearcoat_normal_texture: Option<TextureRef>,
+    // KHR_materials_transmission's factor in red, scaling the material's.
+    pub transmission_texture: Option<TextureRef>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    // index of the glTF or MTL material, shared by primitives that can be drawn together.
+    // None for the default material.
+    pub material_index: Option<usize>,
+    // local space bounding box of the vertices.
+    pub aabb_min: Vec3,
+    pub aabb_max: Vec3,
+}
+
+impl Primitive {
+    // geometry with the default material and no textures. tangents are generated from the UVs.
+    pub fn new(vertices: Vec<crate::renderer::Vertex>, indices: Vec<u32>) -> Self {
+        let (aabb_min, aabb_max) = Self::compute_aabb(&vertices);
+        let mut primitive = Self {
+            vertices,
+            indices,
+            topology: Topology::Triangles,
+            material: Material::default(),
+            base_color_texture: None,
+            normal_texture: None,
+            metallic_roughness_texture: None,
+            occlusion_texture: None,
+            emissive_texture: None,
+            clearcoat_normal_texture: None,
+            transmission_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            material_index: None,
+            aabb_min,
+            aabb_max,
+        };
+        primitive.generate_tangents();
+        primitive
+    }
+
+    // center of the bounding box in local space.
+    pub fn center(&self) -> Vec3 {
+        self.aabb().center()
+    }
+
+    // local space bounding box of the vertices, empty without any.
+    pub fn aabb(&self) -> Aabb {
+        Aabb {
+            min: self.aabb_min,
+            max: self.aabb_max,
+        }
+    }
+
+    fn compute_aabb(vertices: &[crate::renderer::Vertex]) -> (Vec3, Vec3) {
+        vertices.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), vertex| (min.min(vertex.position), max.max(vertex.position)),
+        )
+    }
+
+    // an OBJ mesh loaded with single indices and triangulated. texture paths are relative to `base`.
+    fn from_obj(
+        mesh: &tobj::Mesh,
+        material: Option<(usize, &tobj::Material)>,
+        base: &std::path::Path,
+        images: &mut ImageCache<std::path::PathBuf>,
+    ) -> Result<Self, SceneError> {
+        let positions: Vec<Vec3> = mesh
+            .positions
+            .chunks_exact(3)
+            .map(Vec3::from_slice)
+            .collect();
+        // OBJ's v axis points up.
+        let uvs: Vec<Vec2> = if mesh.texcoords.len() == positions.len() * 2 {
+            mesh.texcoords
+                .chunks_exact(2)
+                .map(|uv| Vec2::new(uv[0], 1.0 - uv[1]))
+                .collect()
+        } else {
+            vec![Vec2::ZERO; positions.len()]
+        };
+        let colors: Vec<Vec4> = if mesh.vertex_color.len() == positions.len() * 3 {
+            mesh.vertex_color
+                .chunks_exact(3)
+                .map(|color| Vec3::from_slice(color).extend(1.0))
+                .collect()
+        } else {
+            vec![Vec4::ONE; positions.len()]
+        };
+        let vertex = |i: usize, normal: Vec3| crate::renderer::Vertex {
+            position: positions[i],
+            normal,
+            uv: uvs[i],
+            tangent: Vec4::ZERO,
+            color: colors[i],
+            joints: [0; 4],
+            weights: Vec4::ZERO,
+        };
+
+        let (vertices, indices): (Vec<crate::renderer::Vertex>, Vec<u32>) =
+            if mesh.normals.len() == mesh.positions.len() {
+                let vertices = mesh
+                    .normals
+                    .chunks_exact(3)
+                    .enumerate()
+                    .map(|(i, normal)| vertex(i, Vec3::from_slice(normal)))
+                    .collect();
+                (vertices, mesh.indices.clone())
+            } else {
+                // without normals every triangle gets its own vertices, with the face normal.
+                let vertices: Vec<crate::renderer::Vertex> = mesh
+                    .indices
+                    .chunks_exact(3)
+                    .flat_map(|triangle| {
+                        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+                        let normal = (b - a).cross(c - a).normalize_or_zero();
+                        triangle.iter().map(move |&i| vertex(i as usize, normal))
+                    })
+                    .collect();
+                let indices = (0..vertices.len() as u32).collect();
+                (vertices, indices)
+            };
+
+        let default_material = tobj::Material::default();
+        let (material_index, obj_material) = match material {
+            Some((index, material)) => (Some(index), material),
+            None => (None, &default_material),
+        };
+        let alpha = obj_material.dissolve.unwrap_or(1.0);
+        let alpha_mode = if alpha < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        };
+        // the PBR extension's Pr and Pm if present, otherwise roughness from the Blinn-Phong exponent.
+        let parameter = |name: &str| {
+            obj_material
+                .unknown_param
+                .get(name)
+                .and_then(|value| value.trim().parse::<f32>().ok())
+        };
+        let roughness = parameter("Pr").unwrap_or_else(|| {
+            obj_material
+                .shininess
+                .map_or(1.0, |shininess| (2.0 / (shininess.max(0.0) + 2.0)).sqrt())
+        });
+        let material = Material {
+            base_color: Vec3::from(obj_material.diffuse.unwrap_or([1.0; 3])).extend(alpha),
+            metallic: parameter("Pm").unwrap_or(0.0),
+            roughness,
+            normal_scale: 1.0,
+            alpha_mode: alpha_mode as u32,
+            alpha_cutoff: 0.5,
+            occlusion_strength: 1.0,
+            ..Default::default()
+        };
+        let mut load_texture = |file: &Option<String>| {
+            file.as_ref()
+                .map(|file| {
+                    let path = base.join(file);
+                    let image = images.get_or_load(path.clone(), || Ok(Image::open(path)?));
+                    image.map(|image| TextureRef {
+                        image,
+                        sampler: Sampler::default(),
+                    })
+                })
+                .transpose()
+        };
+
+        let (aabb_min, aabb_max) = Self::compute_aabb(&vertices);
+        let mut primitive = Self {
+            vertices,
+            indices,
+            topology: Topology::Triangles,
+            material,
+            base_color_texture: load_texture(&obj_material.diffuse_texture)?,
+            normal_texture: load_texture(&obj_material.normal_texture)?,
+            // MTL's roughness and metallic maps are separate images.
+            metallic_roughness_texture: None,
+            occlusion_texture: None,
+            emissive_texture: None,
+            clearcoat_normal_texture: None,
+            transmission_texture: None,
+            alpha_mode,
+            double_sided: false,
+            material_index,
+            aabb_min,
+            aabb_max,
+        };
+        primitive.generate_tangents();
+        Ok(primitive)
+    }
+
+    // replaces the normals with the area weighted average of the normals of each vertex' triangles.
+    // vertices without a triangle, like those of points and lines, face +Y.
+    pub fn generate_normals(&mut self) {
+        let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+        if self.topology == Topology::Triangles {
+            for triangle in self.indices.chunks_exact(3) {
+                let [i0, i1, i2] = [0, 1, 2].map(|i| triangle[i] as usize);
+                let [p0, p1, p2] = [i0, i1, i2].map(|i| self.vertices[i].position);
+                // its length is twice the triangle's area.
+                let normal = (p1 - p0).cross(p2 - p0);
+                for i in [i0, i1, i2] {
+                    normals[i] += normal;
+                }
+            }
+        }
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.try_normalize().unwrap_or(Vec3::Y);
+        }
+    }
+
+    // per-vertex tangents accumulated from the triangles' UV gradients.
+    // vertices without usable UVs (e.g. flat-shaded meshes without texture coordinates)
+    // get a zero tangent, and the shader falls back to the geometric normal for them.
+    fn generate_tangents(&mut self) {
+        let mut tangents = vec![Vec3::ZERO; self.vertices.len()];
+        let mut bitangents = vec![Vec3::ZERO; self.vertices.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [i0, i1, i2] = [0, 1, 2].map(|i| triangle[i] as usize);
+            let [v0, v1, v2] = [i0, i1, i2].map(|i| self.vertices[i]);
+
+            let edge1 = v1.position - v0.position;
+            let edge2 = v2.position - v0.position;
+            let delta_uv1 = v1.uv - v0.uv;
+            let delta_uv2 = v2.uv - v0.uv;
+
+            let det = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+            if det.abs() < f32::EPSILON {
+                continue;
+            }
+            let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / det;
+            let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) / det;
+            for i in [i0, i1, i2] {
+                tangents[i] += tangent;
+                bitangents[i] += bitangent;
+            }
+        }
+
+        for ((vertex, tangent), bitangent) in self.vertices.iter_mut().zip(tangents).zip(bitangents)
+        {
+            let normal = vertex.normal;
+            // Gram-Schmidt orthogonalize against the normal.
+            let tangent = (tangent - normal * normal.dot(tangent)).normalize_or_zero();
+            let handedness = if normal.cross(tangent).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            vertex.tangent = tangent.extend(handedness);
+        }
+    }
+}
+
+// decoded RGBA8 image.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    // six square faces of an environment cube map, named px, nx, py, ny, pz and nz.png in `dir`.
+    pub fn load_cube_map(dir: impl AsRef<std::path::Path>) -> Result<[Self; 6], image::ImageError> {
+        let mut faces = Vec::new();
+        for face in ["px", "nx", "py", "ny", "pz", "nz"] {
+            faces.push(Self::open(dir.as_ref().join(face).with_extension("png"))?);
+        }
+        if faces
+            .iter()
+            .any(|face| face.width != faces[0].width || face.height != faces[0].width)
+        {
+            return Err(image::ImageError::Parameter(
+                image::error::ParameterError::from_kind(
+                    image::error::ParameterErrorKind::DimensionMismatch,
+                ),
+            ));
+        }
+        Ok(faces.try_into().unwrap())
+    }
+
+    // any format the `image` crate reads.
+    fn open(path: impl AsRef<std::path::Path>) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.to_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            pixels: image.into_raw(),
+        })
+    }
+
+    fn from_gltf(data: gltf::image::Data) -> Result<Self, SceneError> {
+        use gltf::image::Format;
+        let gltf::image::Data {
+            pixels,
+            format,
+            width,
+            height,
+        } = data;
+        let image: Option<image::DynamicImage> = match format {
+            Format::R8 => image::GrayImage::from_raw(width, height, pixels).map(Into::into),
+            Format::R8G8 => image::GrayAlphaImage::from_raw(width, height, pixels).map(Into::into),
+            Format::R8G8B8 => image::RgbImage::from_raw(width, height, pixels).map(Into::into),
+            Format::R8G8B8A8 => image::RgbaImage::from_raw(width, height, pixels).map(Into::into),
+            _ => None,
+        };
+        let image = image
+            .ok_or_else(|| SceneError::UnsupportedFeature(format!("{format:?} image format")))?;
+        Ok(Self {
+            width,
+            height,
+            pixels: image.to_rgba8().into_raw(),
+        })
+    }
+}
+
+// decodes each image once, however many textures use it. `K` identifies an image in the source file.
+struct ImageCache<K> {
+    images: Vec<Image>,
+    indices: std::collections::HashMap<K, usize>,
+}
+
+impl<K> Default for ImageCache<K> {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            indices: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + std::hash::Hash> ImageCache<K> {
+    // index into `images` of the image for `key`, loaded with `load` the first time.
+    fn get_or_load(
+        &mut self,
+        key: K,
+        load: impl FnOnce() -> Result<Image, SceneError>,
+    ) -> Result<usize, SceneError> {
+        if let Some(&index) = self.indices.get(&key) {
+            return Ok(index);
+        }
+        self.images.push(load()?);
+        self.indices.insert(key, self.images.len() - 1);
+        Ok(self.images.len() - 1)
+    }
+}
+
+// a material texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureRef {
+    // index into `Scene::images`, shared by every primitive using the same image.
+    pub image: usize,
+    pub sampler: Sampler,
+}
+
+// how a texture is filtered and wrapped, from its glTF sampler.
+// defaults to repeating trilinear filtering, like glTF textures without one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sampler {
+    pub address_mode_u: wgpu::AddressMode,
+    pub address_mode_v: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    // None only samples the full size image, for minification filters without mipmaps.
+    pub mipmap_filter: Option<wgpu::MipmapFilterMode>,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: Some(wgpu::MipmapFilterMode::Linear),
+        }
+    }
+}
+
+impl From<gltf::texture::Sampler<'_>> for Sampler {
+    fn from(value: gltf::texture::Sampler) -> Self {
+        use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+        use wgpu::{FilterMode, MipmapFilterMode};
+
+        let address_mode = |mode| match mode {
+            WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+            WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+        };
+        let mag_filter = match value.mag_filter() {
+            Some(MagFilter::Nearest) => FilterMode::Nearest,
+            Some(MagFilter::Linear) | None => FilterMode::Linear,
+        };
+        let (min_filter, mipmap_filter) = match value.min_filter() {
+            Some(MinFilter::Nearest) => (FilterMode::Nearest, None),
+            Some(MinFilter::Linear) => (FilterMode::Linear, None),
+            Some(MinFilter::NearestMipmapNearest) => {
+                (FilterMode::Nearest, Some(MipmapFilterMode::Nearest))
+            }
+            Some(MinFilter::LinearMipmapNearest) => {
+                (FilterMode::Linear, Some(MipmapFilterMode::Nearest))
+            }
+            Some(MinFilter::NearestMipmapLinear) => {
+                (FilterMode::Nearest, Some(MipmapFilterMode::Linear))
+            }
+            Some(MinFilter::LinearMipmapLinear) | None => {
+                (FilterMode::Linear, Some(MipmapFilterMode::Linear))
+            }
+        };
+        Self {
+            address_mode_u: address_mode(value.wrap_s()),
+            address_mode_v: address_mode(value.wrap_t()),
+            mag_filter,
+            min_filter,
+            mipmap_filter,
+        }
+    }
+}
+
+// how a primitive's indices form shapes. glTF's strips, fans and loops are unrolled into lists.
+// points and lines don't cast shadows or occlude ambient light.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Topology {
+    Triangles,
+    Lines,
+    Points,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlphaMode {
+    Opaque,
+    // discarded below the material's alpha cutoff.
+    Mask,
+    Blend,
+}
+
+impl From<gltf::material::AlphaMode> for AlphaMode {
+    fn from(value: gltf::material::AlphaMode) -> Self {
+        match value {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        }
+    }
 }
 
 #[repr(C, packed)]
 #[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub base_color: Vec4,
     pub metallic: f32,
     pub roughness: f32,
+    pub normal_scale: f32,
+    // 0: opaque, 1: mask, 2: blend
+    pub alpha_mode: u32,
+    pub alpha_cutoff: f32,
+    // how much of the occlusion texture applies, from 0 to 1.
+    pub occlusion_strength: f32,
+    // `Material::UNLIT` and other shading options.
+    pub flags: u32,
+    pub _pad2: f32,
+    // maps vertex UVs to texture coordinates, set with `set_uv_transform`.
+    // the columns of a Mat3, each padded to a vec4 like WGSL's mat3x3f.
+    pub uv_transform: [Vec4; 3],
+    // emitted radiance, with KHR_materials_emissive_strength already applied.
+    pub emissive: Vec3,
+    // KHR_materials_clearcoat, used when `flags` has `Material::CLEARCOAT`.
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+    pub clearcoat_normal_scale: f32,
+    // KHR_materials_transmission, used when `flags` has `Material::TRANSMISSION`.
+    pub transmission: f32,
+    pub _pad4: f32,
+}
+
+impl Material {
+    // KHR_materials_unlit: the base color is output as is, without lighting.
+    // must match shader.wgsl
+    pub const UNLIT: u32 = 1;
+    // KHR_materials_clearcoat: a second specular lobe layered over the base material.
+    // must match shader.wgsl
+    pub const CLEARCOAT: u32 = 2;
+    // KHR_materials_transmission: the base lets light through from behind the surface.
+    // must match shader.wgsl
+    pub const TRANSMISSION: u32 = 4;
+
+    pub fn set_uv_transform(&mut self, transform: Mat3) {
+        self.uv_transform = [
+            transform.x_axis.extend(0.0),
+            transform.y_axis.extend(0.0),
+            transform.z_axis.extend(0.0),
+        ];
+    }
+}
+
+// glTF's default material, used by every primitive without a material index.
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            base_color: Vec4::ONE,
+            metallic: 1.0,
+            roughness: 1.0,
+            normal_scale: 1.0,
+            alpha_mode: AlphaMode::Opaque as u32,
+            alpha_cutoff: 0.5,
+            occlusion_strength: 1.0,
+            flags: 0,
+            _pad2: 0.0,
+            uv_transform: [Vec4::X, Vec4::Y, Vec4::Z],
+            emissive: Vec3::ZERO,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            clearcoat_normal_scale: 1.0,
+            transmission: 0.0,
+            _pad4: 0.0,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mesh {
     pub name: Option<String>,
+    // world space, recomputed from `node` by `Scene::update_transforms`.
     pub transform: Transform,
     pub primitives: Vec<Primitive>,
+    // index of the glTF mesh. nodes using the same mesh share its primitives' geometry.
+    pub mesh_index: usize,
+    // index into `Scene::nodes`.
+    pub node: usize,
+    // index into `Scene::skins`. skinned vertices are placed by the joints, not by `transform`.
+    pub skin: Option<usize>,
+}
+
+// shapes from `shapes` with one primitive, placed by `Scene::add_mesh` at their `transform`.
+impl Mesh {
+    // side length 1.
+    pub fn cube() -> Self {
+        Self::with_primitive("cube", shapes::cube(1.0))
+    }
+
+    // diameter 1, with `subdivisions` rings from pole to pole and twice as many segments around.
+    pub fn sphere(subdivisions: u32) -> Self {
+        Self::with_primitive("sphere", shapes::uv_sphere(subdivisions, subdivisions * 2))
+    }
+
+    // side length 1 in the xz plane, facing +y.
+    pub fn plane() -> Self {
+        Self::with_primitive("plane", shapes::plane(1))
+    }
+
+    fn with_primitive(name: &str, primitive: Primitive) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            transform: Mat4::IDENTITY.into(),
+            primitives: vec![primitive],
+            mesh_index: 0,
+            node: 0,
+            skin: None,
+        }
+    }
+
+    // world space bounds of the primitives placed by `transform`. skinned meshes are bounded
+    // in their bind pose, the joints may move them elsewhere.
+    pub fn aabb(&self) -> Aabb {
+        let matrix = self.transform.matrix();
+        self.primitives.iter().fold(Aabb::EMPTY, |aabb, primitive| {
+            aabb.union(primitive.aabb().transform(matrix))
+        })
+    }
+}
+
+// a glTF node, indexed the same as in the file.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub parent: Option<usize>,
+    // relative to the parent.
+    pub transform: Transform,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skin {
+    // indices into `Scene::nodes`.
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Mat4>,
+    // joint world matrix * inverse bind matrix, per joint. recomputed by `Scene::update_transforms`.
+    pub joint_matrices: Vec<Mat4>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnimationPath {
+    Translation,
+    // xyzw quaternions.
+    Rotation,
+    Scale,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // keyframes hold an in-tangent, value and out-tangent per time.
+    CubicSpline,
+}
+
+// keyframes for one property of one node.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimationChannel {
+    // index into `Scene::nodes`.
+    pub node: usize,
+    pub path: AnimationPath,
+    pub interpolation: Interpolation,
+    // seconds, ascending.
+    pub times: Vec<f32>,
+    // translations and scales leave w unused.
+    pub keyframes: Vec<Vec4>,
+}
+
+impl AnimationChannel {
+    pub fn sample(&self, time: f32) -> Vec4 {
+        let value = |i: usize| match self.interpolation {
+            Interpolation::CubicSpline => self.keyframes[3 * i + 1],
+            _ => self.keyframes[i],
+        };
+        let last = self.times.len() - 1;
+        // clamped outside the keyframes.
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return value(0);
+        }
+        if next > last {
+            return value(last);
+        }
+        let prev = next - 1;
+        let dt = self.times[next] - self.times[prev];
+        let t = (time - self.times[prev]) / dt;
+
+        match self.interpolation {
+            Interpolation::Step => value(prev),
+            Interpolation::Linear => match self.path {
+                AnimationPath::Rotation => Quat::from_vec4(value(prev))
+                    .slerp(Quat::from_vec4(value(next)), t)
+                    .into(),
+                _ => value(prev).lerp(value(next), t),
+            },
+            Interpolation::CubicSpline => {
+                // hermite spline, tangents are scaled by the keyframe interval.
+                let out_tangent = self.keyframes[3 * prev + 2] * dt;
+                let in_tangent = self.keyframes[3 * next] * dt;
+                let t2 = t * t;
+                let t3 = t2 * t;
+                let v = (2.0 * t3 - 3.0 * t2 + 1.0) * value(prev)
+                    + (t3 - 2.0 * t2 + t) * out_tangent
+                    + (-2.0 * t3 + 3.0 * t2) * value(next)
+                    + (t3 - t2) * in_tangent;
+                match self.path {
+                    AnimationPath::Rotation => v.normalize(),
+                    _ => v,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Animation {
+    pub name: Option<String>,
+    pub channels: Vec<AnimationChannel>,
+    // time of the last keyframe in any channel. playback loops over it.
+    pub duration: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection {
+    Perspective,
+    // half extents of the view volume.
+    Orthographic { xmag: f32, ymag: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CameraMode {
+    // the scene's own camera.
+    Normal,
+    // orthographic view looking straight down from `Camera::top_down_height`, for debugging scene layout.
+    TopDown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Camera {
     pub position: Vec3,
     pub yaw: f32,
     pub pitch: f32,
     pub roll: f32,
 
+    pub projection: Projection,
     pub yfov: f32,
     pub zfar: Option<f32>,
     pub znear: f32,
+
+    pub mode: CameraMode,
+    pub top_down_height: f32,
+}
+
+// at the origin looking down -z, for scenes without a camera.
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            position: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            roll: 0.0,
+            projection: Projection::Perspective,
+            yfov: 1.0,
+            zfar: None,
+            znear: 0.001,
+            mode: CameraMode::Normal,
+            top_down_height: 20.0,
+        }
+    }
 }
 
 impl Camera {
+    // writes the camera as JSON, to return to the same viewpoint with `load`.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    // moves the camera back along its view direction until the sphere around `aabb` fits the vertical
+    // field of view, extending `zfar` to reach past it. empty boxes leave the camera as it is.
+    pub fn frame(&mut self, aabb: Aabb) {
+        if aabb.is_empty() {
+            return;
+        }
+        let center = aabb.center();
+        let radius = (0.5 * aabb.size().length()).max(self.znear);
+        let distance = match self.projection {
+            Projection::Perspective => radius / (0.5 * self.yfov).sin(),
+            // the view doesn't widen with distance, the bounds only have to be past the near plane.
+            Projection::Orthographic { .. } => radius + self.znear,
+        };
+        self.position = center - self.direction() * distance;
+        if let Some(zfar) = &mut self.zfar {
+            *zfar = zfar.max(distance + radius);
+        }
+    }
+
+    // puts the clip planes just around the sphere around `aabb`, so the depth buffer's precision
+    // is spent on the scene. the near plane stays a small fraction of the far one from inside it.
+    // empty boxes leave the planes as they are.
+    pub fn fit_clip_planes(&mut self, aabb: Aabb) {
+        // nearest plane relative to the far one, reverse-Z keeps depth precise down to that.
+        const MIN_NEAR_RATIO: f32 = 1e-5;
+        let radius = 0.5 * aabb.size().length();
+        let distance = self.eye().distance(aabb.center());
+        let zfar = distance + radius;
+        // a single point at the eye has no depth to fit.
+        if aabb.is_empty() || zfar <= 0.0 {
+            return;
+        }
+        self.znear = (distance - radius).max(zfar * MIN_NEAR_RATIO);
+        self.zfar = Some(zfar);
+    }
+
     pub fn get_matrix(&self, aspect_ratio: f32) -> Mat4 {
-        (match self.zfar {
-            Some(zfar) => Mat4::perspective_rh(self.yfov, aspect_ratio, self.znear, zfar),
-            None => Mat4::perspective_infinite_rh(self.yfov, aspect_ratio, self.znear),
-        }) * Mat4::look_to_rh(self.position, self.direction(), self.up_vec())
+        self.projection_matrix(aspect_ratio, self.znear, self.zfar) * self.view_matrix()
+    }
+
+    // world space ray through `ndc`, with x and y from -1 to 1 and y up, starting at the near plane.
+    // returns its origin and unit direction, for picking what's under the cursor.
+    pub fn ray(&self, aspect_ratio: f32, ndc: Vec2) -> (Vec3, Vec3) {
+        let inverse = self.get_matrix(aspect_ratio).inverse();
+        // depth is reversed, 1 is the near plane. 0.5 is beyond it even without a far plane.
+        let near = inverse.project_point3(ndc.extend(1.0));
+        let beyond = inverse.project_point3(ndc.extend(0.5));
+        (near, (beyond - near).normalize())
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_to_rh(self.eye(), self.direction(), self.up_vec())
+    }
+
+    // reverse-Z: depth is 1 at the near plane and 0 at the far plane (or infinity), which balances
+    // float precision against the perspective divide and keeps distant surfaces from z-fighting.
+    fn projection_matrix(&self, aspect_ratio: f32, znear: f32, zfar: Option<f32>) -> Mat4 {
+        // z' = w - z
+        const REVERSE_Z: Mat4 = Mat4::from_cols(
+            Vec4::X,
+            Vec4::Y,
+            Vec4::new(0.0, 0.0, -1.0, 0.0),
+            Vec4::new(0.0, 0.0, 1.0, 1.0),
+        );
+        REVERSE_Z * self.forward_projection_matrix(aspect_ratio, znear, zfar)
+    }
+
+    // maps the near plane to depth 0 and the far plane to 1.
+    fn forward_projection_matrix(&self, aspect_ratio: f32, znear: f32, zfar: Option<f32>) -> Mat4 {
+        if self.mode == CameraMode::TopDown {
+            // the field of view sets how much of the ground is visible from the top-down height.
+            let ymag = self.top_down_height * (self.yfov / 2.0).tan();
+            let zfar = zfar.unwrap_or(znear + 1000.0);
+            return Mat4::orthographic_rh(
+                -ymag * aspect_ratio,
+                ymag * aspect_ratio,
+                -ymag,
+                ymag,
+                znear,
+                zfar,
+            );
+        }
+        match self.projection {
+            Projection::Perspective => match zfar {
+                Some(zfar) => Mat4::perspective_rh(self.yfov, aspect_ratio, znear, zfar),
+                None => Mat4::perspective_infinite_rh(self.yfov, aspect_ratio, znear),
+            },
+            Projection::Orthographic { xmag, ymag } => {
+                // keep the authored extents visible, widening whichever axis the viewport has extra room on.
+                let (xmag, ymag) = if aspect_ratio > xmag / ymag {
+                    (ymag * aspect_ratio, ymag)
+                } else {
+                    (xmag, xmag / aspect_ratio)
+                };
+                let zfar = zfar.unwrap_or(znear + 1000.0);
+                Mat4::orthographic_rh(-xmag, xmag, -ymag, ymag, znear, zfar)
+            }
+        }
+    }
+
+    // world space corners of the part of the view frustum between `znear` and `zfar`.
+    pub fn frustum_corners(&self, aspect_ratio: f32, znear: f32, zfar: f32) -> [Vec3; 8] {
+        let inverse = (self.projection_matrix(aspect_ratio, znear, Some(zfar))
+            * self.view_matrix())
+        .inverse();
+        let mut corners = [Vec3::ZERO; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let ndc = Vec3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { 1.0 } else { 0.0 },
+            );
+            *corner = inverse.project_point3(ndc);
+        }
+        corners
+    }
+
+    // world space planes bounding the view, as (normal, distance) with the normal pointing inward.
+    // a point p is inside all of them when dot(normal, p) + distance >= 0.
+    pub fn frustum_planes(&self, aspect_ratio: f32) -> [Vec4; 6] {
+        // rows of the view projection matrix, combined per clip space bound (Gribb & Hartmann).
+        let matrix = self.get_matrix(aspect_ratio);
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| matrix.row(i));
+        // depth is reversed, so the near plane is z <= w and the far plane z >= 0.
+        // without `zfar` the far plane never rejects anything.
+        [w + x, w - x, w + y, w - y, w - z, z]
+    }
+
+    // where the view is rendered from. differs from `position` in top-down mode.
+    pub fn eye(&self) -> Vec3 {
+        match self.mode {
+            CameraMode::Normal => self.position,
+            CameraMode::TopDown => {
+                Vec3::new(self.position.x, self.top_down_height, self.position.z)
+            }
+        }
     }
 
     pub fn direction(&self) -> Vec3 {
-        Quat::from_euler(glam::EulerRot::ZXYEx, self.roll, self.pitch, self.yaw) * Vec3::NEG_Z
+        match self.mode {
+            CameraMode::Normal => {
+                Quat::from_euler(glam::EulerRot::ZXYEx, self.roll, self.pitch, self.yaw)
+                    * Vec3::NEG_Z
+            }
+            CameraMode::TopDown => Vec3::NEG_Y,
+        }
     }
 
     fn up_vec(&self) -> Vec3 {
-        Quat::from_euler(glam::EulerRot::ZXYEx, self.roll, self.pitch, self.yaw) * Vec3::Y
+        match self.mode {
+            CameraMode::Normal => {
+                Quat::from_euler(glam::EulerRot::ZXYEx, self.roll, self.pitch, self.yaw) * Vec3::Y
+            }
+            // keep the walking direction pointing up on screen.
+            CameraMode::TopDown => self.forward_vec(),
+        }
     }
 
     pub fn forward_vec(&self) -> Vec3 {
         Vec3::NEG_Z.rotate_axis(Vec3::Y, self.yaw)
     }
 
+    // right on screen, tilted with the roll.
+    pub fn right_vec(&self) -> Vec3 {
+        match self.mode {
+            CameraMode::Normal => {
+                Quat::from_euler(glam::EulerRot::ZXYEx, self.roll, self.pitch, self.yaw) * Vec3::X
+            }
+            CameraMode::TopDown => self.forward_vec().cross(Vec3::Y),
+        }
+    }
+
+    // the camera's position and orientation, with a unit scale.
+    pub fn transform(&self) -> Transform {
+        Transform {
+            translation: self.position,
+            rotation: Quat::from_euler(glam::EulerRot::ZXYEx, self.roll, self.pitch, self.yaw),
+            scale: Vec3::ONE,
+        }
+    }
+
+    // places the camera like `transform`, ignoring its scale.
+    pub fn set_transform(&mut self, transform: &Transform) {
+        self.position = transform.translation;
+        (self.yaw, self.pitch, self.roll) = Self::yaw_pitch_roll(transform.rotation);
+    }
+
     fn yaw_pitch_roll(quat: Quat) -> (f32, f32, f32) {
         let (roll, pitch, yaw) = quat.to_euler(glam::EulerRot::ZXYEx);
         (yaw, pitch, roll)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Light {
     Point {
         pos: Vec3,
@@ -119,6 +1135,50 @@ pub enum Light {
     },
 }
 
+// a directional light placed by where it is in the sky, lighting the scene besides its own lights.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sun {
+    // radians around +Y from -Z, turning towards +X.
+    pub azimuth: f32,
+    // radians above the horizon.
+    pub elevation: f32,
+    pub color: Vec3,
+    pub radiant_flux: f32,
+}
+
+impl Default for Sun {
+    fn default() -> Self {
+        Self {
+            azimuth: std::f32::consts::FRAC_PI_4,
+            elevation: std::f32::consts::FRAC_PI_4,
+            color: Vec3::ONE,
+            radiant_flux: 10.0,
+        }
+    }
+}
+
+impl Sun {
+    // the direction its light travels in, from the sky towards the scene.
+    pub fn direction(&self) -> Vec3 {
+        let (sin_azimuth, cos_azimuth) = self.azimuth.sin_cos();
+        let (sin_elevation, cos_elevation) = self.elevation.sin_cos();
+        -Vec3::new(
+            cos_elevation * sin_azimuth,
+            sin_elevation,
+            -cos_elevation * cos_azimuth,
+        )
+    }
+
+    pub fn light(&self) -> Light {
+        Light::Directional {
+            direction: self.direction(),
+            color: self.color,
+            radiant_flux: self.radiant_flux,
+        }
+    }
+}
+
 impl Light {
     pub fn raw(&self) -> LightRaw {
         let matrix = self.matrix();
@@ -196,6 +1256,71 @@ impl Light {
             }
         }
     }
+
+    // matrices rendering the six cube map faces around a point light, in +x, -x, +y, -y, +z, -z order.
+    // cube maps are addressed left-handed, so the faces use left-handed views.
+    pub fn cube_face_matrices(&self, zfar: f32) -> [Mat4; 6] {
+        let pos = match self {
+            Light::Point { pos, .. } => *pos,
+            _ => Vec3::ZERO,
+        };
+        let projection = Mat4::perspective_lh(std::f32::consts::FRAC_PI_2, 1.0, 0.05, zfar);
+        [
+            (Vec3::X, Vec3::Y),
+            (Vec3::NEG_X, Vec3::Y),
+            (Vec3::Y, Vec3::NEG_Z),
+            (Vec3::NEG_Y, Vec3::Z),
+            (Vec3::Z, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::Y),
+        ]
+        .map(|(direction, up)| projection * Mat4::look_to_lh(pos, direction, up))
+    }
+
+    // orthographic light space matrix enclosing `corners`, used for the directional light's cascades.
+    // the near plane is pulled back by `caster_distance` so that occluders outside the slice still cast shadows into it.
+    // the bounds are a sphere snapped to the texels of a `resolution`^2 shadow map, so that shadow edges
+    // don't swim as the camera moves and turns. other lights use `matrix`.
+    pub fn cascade_matrix(
+        &self,
+        corners: &[Vec3; 8],
+        caster_distance: f32,
+        resolution: u32,
+    ) -> Mat4 {
+        let Light::Directional { direction, .. } = self else {
+            return self.matrix();
+        };
+        let direction = direction.normalize();
+        let up = if direction.y.abs() > 0.99 {
+            Vec3::Z
+        } else {
+            Vec3::Y
+        };
+        // the view is anchored at the origin so its texel grid stays in place between frames.
+        let view = Mat4::look_to_rh(Vec3::ZERO, direction, up);
+
+        // unlike a box fitted to the corners, the sphere's size doesn't change as the camera turns.
+        // it's rounded up so that float noise doesn't change the texel size either.
+        let center = corners.iter().sum::<Vec3>() / 8.0;
+        let radius = corners
+            .iter()
+            .map(|corner| corner.distance(center))
+            .fold(0.0, f32::max);
+        let radius = (radius * 16.0).ceil() / 16.0;
+
+        let texel_size = 2.0 * radius / resolution as f32;
+        let center = view.transform_point3(center);
+        let snapped = (center.truncate() / texel_size).floor() * texel_size;
+
+        // the view looks down -z, so depth along the light is -z.
+        Mat4::orthographic_rh(
+            snapped.x - radius,
+            snapped.x + radius,
+            snapped.y - radius,
+            snapped.y + radius,
+            -center.z - radius - caster_distance,
+            -center.z + radius,
+        ) * view
+    }
 }
 
 #[repr(C, packed)]
@@ -214,34 +1339,505 @@ pub struct LightRaw {
     pub _pad3: f32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Gltf(gltf::Error),
+    Obj(tobj::LoadError),
+    Image(image::ImageError),
+    MissingAttribute(&'static str),
+    UnsupportedFeature(String),
+    // a glTF scene index past the file's scenes.
+    MissingScene(usize),
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+}
+
+impl core::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "failed to read scene file: {err}"),
+            SceneError::Gltf(err) => write!(f, "invalid glTF: {err}"),
+            SceneError::Obj(err) => write!(f, "invalid OBJ: {err}"),
+            SceneError::Image(err) => write!(f, "failed to load image: {err}"),
+            SceneError::MissingAttribute(attribute) => {
+                write!(f, "primitive is missing required attribute {attribute}")
+            }
+            SceneError::UnsupportedFeature(feature) => write!(f, "unsupported feature: {feature}"),
+            SceneError::MissingScene(index) => write!(f, "there is no scene {index} in the file"),
+            #[cfg(feature = "serde")]
+            SceneError::Json(err) => write!(f, "invalid scene JSON: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            SceneError::Gltf(err) => Some(err),
+            SceneError::Obj(err) => Some(err),
+            SceneError::Image(err) => Some(err),
+            #[cfg(feature = "serde")]
+            SceneError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(value: std::io::Error) -> Self {
+        SceneError::Io(value)
+    }
+}
+
+impl From<gltf::Error> for SceneError {
+    fn from(value: gltf::Error) -> Self {
+        SceneError::Gltf(value)
+    }
+}
+
+impl From<tobj::LoadError> for SceneError {
+    fn from(value: tobj::LoadError) -> Self {
+        SceneError::Obj(value)
+    }
+}
+
+impl From<image::ImageError> for SceneError {
+    fn from(value: image::ImageError) -> Self {
+        SceneError::Image(value)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for SceneError {
+    fn from(value: serde_json::Error) -> Self {
+        SceneError::Json(value)
+    }
+}
+
+// tells scenes and their changes apart, see `Scene::mark_changed`. every new one is different
+// from any before it, also across scenes, so the renderer notices a scene being swapped out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SceneVersion(u64);
+
+impl Default for SceneVersion {
+    fn default() -> Self {
+        static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scene {
     pub camera: Camera,
     pub lights: Vec<Light>,
+    // lights the scene whatever lights it has, see `all_lights`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sun: Option<Sun>,
     pub meshes: Vec<Mesh>,
+    pub nodes: Vec<Node>,
+    pub skins: Vec<Skin>,
+    pub animations: Vec<Animation>,
+    // decoded texture images, indexed by the primitives' textures.
+    pub images: Vec<Image>,
+    // the renderer only rebuilds its draws when `version` changed, and only updates the joint
+    // matrices of skins when `transforms_version` did.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    version: SceneVersion,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    transforms_version: SceneVersion,
 }
 impl Scene {
-    pub fn from_glb(path: &str) -> Result<Self, ()> {
-        let file = std::fs::File::open(path).map_err(|_| ())?;
+    // an empty scene seen from the default camera, to be filled with `add_mesh` and `add_light`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the lights the renderer draws with: the sun, when there is one, followed by `lights`.
+    // the sun comes first, so it always casts shadows.
+    pub fn all_lights(&self) -> Vec<Light> {
+        self.sun
+            .iter()
+            .map(Sun::light)
+            .chain(self.lights.iter().cloned())
+            .collect()
+    }
+
+    // call after changing `meshes` or their primitives directly, the renderer only uploads them
+    // again once the scene is marked changed. the methods here mark it themselves.
+    // the camera and lights are uploaded every frame, they don't need it.
+    pub fn mark_changed(&mut self) {
+        self.version = SceneVersion::default();
+        self.mark_transforms_changed();
+    }
+
+    // for changes of the meshes' transforms and skins' joint matrices only, which the renderer
+    // updates without rebuilding the geometry. `update_transforms` marks it.
+    pub fn mark_transforms_changed(&mut self) {
+        self.transforms_version = SceneVersion::default();
+    }
+
+    pub fn version(&self) -> SceneVersion {
+        self.version
+    }
+
+    pub fn transforms_version(&self) -> SceneVersion {
+        self.transforms_version
+    }
+
+    // adds `mesh` as a root node placed at its `transform`, with geometry of its own.
+    // returns its index in `meshes`.
+    pub fn add_mesh(&mut self, mut mesh: Mesh) -> usize {
+        mesh.node = self.nodes.len();
+        mesh.mesh_index = self
+            .meshes
+            .iter()
+            .map(|mesh| mesh.mesh_index + 1)
+            .max()
+            .unwrap_or(0);
+        self.nodes.push(Node {
+            parent: None,
+            transform: mesh.transform.clone(),
+        });
+        self.meshes.push(mesh);
+        self.mark_changed();
+        self.meshes.len() - 1
+    }
+
+    // returns its index in `lights`. only the first few lights cast shadows.
+    pub fn add_light(&mut self, light: Light) -> usize {
+        self.lights.push(light);
+        self.lights.len() - 1
+    }
+
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+    }
+
+    // reference scene for checking the shading: a grid of spheres facing the camera, metallic
+    // from 0 to 1 left to right and roughness from 0 to 1 bottom to top.
+    pub fn material_sweep() -> Self {
+        const STEPS: usize = 5;
+        let mut scene = Self::new();
+        for row in 0..STEPS {
+            for column in 0..STEPS {
+                let mut sphere = Mesh::sphere(32);
+                sphere.transform.translation = 1.25
+                    * Vec3::new(
+                        column as f32 - (STEPS - 1) as f32 / 2.0,
+                        row as f32 - (STEPS - 1) as f32 / 2.0,
+                        0.0,
+                    );
+                let material = &mut sphere.primitives[0].material;
+                material.base_color = Vec4::new(0.9, 0.6, 0.2, 1.0);
+                material.metallic = column as f32 / (STEPS - 1) as f32;
+                material.roughness = row as f32 / (STEPS - 1) as f32;
+                scene.add_mesh(sphere);
+            }
+        }
+        scene.add_light(Light::Directional {
+            direction: Vec3::new(-0.5, -0.5, -1.0).normalize(),
+            color: Vec3::ONE,
+            radiant_flux: 10.0,
+        });
+        scene.frame_default_camera();
+        scene
+    }
+
+    // replaces the normals of every primitive with generated smooth ones, and the tangents of its
+    // triangles with ones generated from those. for files exported with broken normals.
+    pub fn recompute_normals(&mut self) {
+        let primitives: Vec<&mut Primitive> = self
+            .meshes
+            .iter_mut()
+            .flat_map(|mesh| mesh.primitives.iter_mut())
+            .collect();
+        for_each_parallel(primitives, |primitive| {
+            primitive.generate_normals();
+            if primitive.topology == Topology::Triangles {
+                primitive.generate_tangents();
+            }
+        });
+        self.mark_changed();
+    }
+
+    // the closest mesh hit by the ray from `origin` along `direction`, as its index in `meshes` and
+    // the distance to the hit in units of `direction`'s length. only triangles are hit, from
+    // either side, and skinned meshes in their bind pose. the triangles of a primitive are only
+    // tested when the ray hits its bounding box before anything closer.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<(usize, f32)> {
+        let mut closest: Option<(usize, f32)> = None;
+        for (mesh_index, mesh) in self.meshes.iter().enumerate() {
+            // in the mesh's space, where the bounds and vertices are. distances in units of the
+            // transformed direction are the same as in world space.
+            let inverse = mesh.transform.matrix().inverse();
+            let local_origin = inverse.transform_point3(origin);
+            let local_direction = inverse.transform_vector3(direction);
+            for primitive in mesh.primitives.iter() {
+                let max_distance = closest.map_or(f32::INFINITY, |(_, distance)| distance);
+                if primitive.topology != Topology::Triangles
+                    || !primitive
+                        .aabb()
+                        .ray_intersection(local_origin, local_direction)
+                        .is_some_and(|distance| distance < max_distance)
+                {
+                    continue;
+                }
+                for triangle in primitive.indices.chunks_exact(3) {
+                    let corners =
+                        [0, 1, 2].map(|i| primitive.vertices[triangle[i] as usize].position);
+                    if let Some(distance) =
+                        ray_triangle_intersection(local_origin, local_direction, corners)
+                        && closest.is_none_or(|(_, closest)| distance < closest)
+                    {
+                        closest = Some((mesh_index, distance));
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    // world space bounds of every mesh, None for a scene without geometry.
+    pub fn bounds(&self) -> Option<Aabb> {
+        let aabb = self
+            .meshes
+            .iter()
+            .fold(Aabb::EMPTY, |aabb, mesh| aabb.union(mesh.aabb()));
+        (!aabb.is_empty()).then_some(aabb)
+    }
+
+    // picks the loader by extension: .obj files with `from_obj`, .json files with `from_json`
+    // when the `serde` feature is on, anything else with `from_glb`.
+    pub fn load(path: &str) -> Result<Self, SceneError> {
+        let has_extension = |expected: &str| {
+            std::path::Path::new(path)
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case(expected))
+        };
+        if has_extension("obj") {
+            return Self::from_obj(path);
+        }
+        #[cfg(feature = "serde")]
+        if has_extension("json") {
+            return Self::from_json(path);
+        }
+        Self::from_glb(path)
+    }
+
+    // writes the whole scene as JSON, images included, to load again with `from_json` without
+    // parsing the original file.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), SceneError> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json(path: impl AsRef<std::path::Path>) -> Result<Self, SceneError> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    // loads the file's default scene, or its first one if it doesn't name a default.
+    pub fn from_glb(path: &str) -> Result<Self, SceneError> {
+        Self::from_glb_scene(path, None)
+    }
+
+    // loads scene `index` of the file, see `glb_scene_names`. None picks the default like `from_glb`.
+    pub fn from_glb_scene(path: &str, index: Option<usize>) -> Result<Self, SceneError> {
+        let file = std::fs::File::open(path)?;
         let reader = std::io::BufReader::new(file);
-        let gltf = gltf::Gltf::from_reader(reader).map_err(|_| ())?;
+        let gltf = gltf::Gltf::from_reader(reader)?;
+
+        // external buffers are resolved relative to the glTF file.
+        Self::from_gltf(gltf, std::path::Path::new(path).parent(), index)
+    }
 
-        let visitor = Visitor::visit(gltf);
+    // names of the file's scenes in index order, None where a scene has no name.
+    pub fn glb_scene_names(path: &str) -> Result<Vec<Option<String>>, SceneError> {
+        let gltf = gltf::Gltf::open(path)?;
+        Ok(gltf
+            .scenes()
+            .map(|scene| scene.name().map(str::to_string))
+            .collect())
+    }
 
-        assert!(visitor.lights.len() <= 4);
-        Ok(Self {
-            camera: visitor.camera.unwrap_or(Camera {
-                position: Vec3::ZERO,
-                yaw: 0.0,
-                pitch: 0.0,
-                roll: 0.0,
-                yfov: 1.0,
-                zfar: None,
-                znear: 0.001,
-            }),
+    // a .glb or .gltf already in memory, e.g. from `include_bytes!`.
+    // there is no directory to resolve external files against, so buffers and images must be embedded.
+    pub fn from_glb_bytes(bytes: &[u8]) -> Result<Self, SceneError> {
+        Self::from_gltf(gltf::Gltf::from_slice(bytes)?, None, None)
+    }
+
+    // `base` is the directory external files are resolved against.
+    fn from_gltf(
+        gltf: gltf::Gltf,
+        base: Option<&std::path::Path>,
+        scene_index: Option<usize>,
+    ) -> Result<Self, SceneError> {
+        let visitor = Visitor::visit(gltf, base, scene_index)?;
+
+        if let Some(skin) = visitor
+            .skins
+            .iter()
+            .find(|skin| skin.joints.len() > crate::renderer::MAX_JOINTS)
+        {
+            return Err(SceneError::UnsupportedFeature(format!(
+                "skin with {} joints (at most {} are supported)",
+                skin.joints.len(),
+                crate::renderer::MAX_JOINTS
+            )));
+        }
+        let has_camera = visitor.camera.is_some();
+        let mut scene = Self {
+            camera: visitor.camera.unwrap_or_default(),
             lights: visitor.lights,
+            sun: None,
             meshes: visitor.meshes,
-        })
+            nodes: visitor.nodes,
+            skins: visitor.skins,
+            animations: visitor.animations,
+            images: visitor.images.images,
+            version: SceneVersion::default(),
+            transforms_version: SceneVersion::default(),
+        };
+        scene.update_transforms();
+        if !has_camera {
+            scene.frame_default_camera();
+        }
+        Ok(scene)
+    }
+
+    // Wavefront OBJ with the materials of the MTL files it references, one mesh per OBJ object.
+    // OBJ has no cameras or lights, so the scene gets a directional light and the default camera, framing it.
+    pub fn from_obj(path: &str) -> Result<Self, SceneError> {
+        let (models, materials) = tobj::load_obj(
+            path,
+            &tobj::LoadOptions {
+                single_index: true,
+                triangulate: true,
+                ..Default::default()
+            },
+        )?;
+        // objects referencing a missing MTL file get the default material.
+        let materials = materials.unwrap_or_default();
+        let base = std::path::Path::new(path)
+            .parent()
+            .unwrap_or(std::path::Path::new(""));
+
+        let mut meshes = Vec::new();
+        let mut nodes = Vec::new();
+        let mut images = ImageCache::default();
+        for (index, model) in models.into_iter().enumerate() {
+            let material = model
+                .mesh
+                .material_id
+                .and_then(|id| materials.get(id).map(|material| (id, material)));
+            let primitive = Primitive::from_obj(&model.mesh, material, base, &mut images)?;
+            nodes.push(Node {
+                parent: None,
+                transform: Mat4::IDENTITY.into(),
+            });
+            meshes.push(Mesh {
+                name: Some(model.name),
+                transform: Mat4::IDENTITY.into(),
+                primitives: vec![primitive],
+                mesh_index: index,
+                node: index,
+                skin: None,
+            });
+        }
+
+        let mut scene = Self {
+            camera: Camera::default(),
+            lights: vec![Light::Directional {
+                direction: Vec3::new(-0.5, -0.5, -1.0).normalize(),
+                color: Vec3::ONE,
+                radiant_flux: 10.0,
+            }],
+            sun: None,
+            meshes,
+            nodes,
+            skins: Vec::new(),
+            animations: Vec::new(),
+            images: images.images,
+            version: SceneVersion::default(),
+            transforms_version: SceneVersion::default(),
+        };
+        scene.update_transforms();
+        scene.frame_default_camera();
+        Ok(scene)
+    }
+
+    // for files without a camera: the default one looks at the origin, which is often inside or
+    // beside the model, so it's moved back until the whole scene is in view.
+    fn frame_default_camera(&mut self) {
+        if let Some(bounds) = self.bounds() {
+            self.camera.frame(bounds);
+        }
+    }
+
+    // plays every animation, each looping over its own duration. `time` is in seconds.
+    pub fn update(&mut self, time: f32) {
+        if self.animations.is_empty() {
+            return;
+        }
+        for animation in self.animations.iter() {
+            let time = if animation.duration > 0.0 {
+                time % animation.duration
+            } else {
+                0.0
+            };
+            for channel in animation.channels.iter() {
+                let value = channel.sample(time);
+                let transform = &mut self.nodes[channel.node].transform;
+                match channel.path {
+                    AnimationPath::Translation => transform.translation = value.truncate(),
+                    AnimationPath::Rotation => transform.rotation = Quat::from_vec4(value),
+                    AnimationPath::Scale => transform.scale = value.truncate(),
+                }
+            }
+        }
+        self.update_transforms();
+    }
+
+    // propagates node transforms into the meshes' world transforms and the skins' joint matrices.
+    pub fn update_transforms(&mut self) {
+        let mut world = vec![None; self.nodes.len()];
+        for node in 0..self.nodes.len() {
+            Self::world_matrix(&self.nodes, &mut world, node);
+        }
+        let world: Vec<Mat4> = world.into_iter().map(Option::unwrap).collect();
+
+        for mesh in self.meshes.iter_mut() {
+            mesh.transform = world[mesh.node].into();
+        }
+        for skin in self.skins.iter_mut() {
+            skin.joint_matrices = skin
+                .joints
+                .iter()
+                .zip(skin.inverse_bind_matrices.iter())
+                .map(|(&joint, inverse_bind_matrix)| world[joint] * *inverse_bind_matrix)
+                .collect();
+        }
+        self.mark_transforms_changed();
+    }
+
+    // memoized in `world`, since nodes aren't ordered parent first.
+    fn world_matrix(nodes: &[Node], world: &mut [Option<Mat4>], node: usize) -> Mat4 {
+        if let Some(matrix) = world[node] {
+            return matrix;
+        }
+        let local = nodes[node].transform.matrix();
+        let matrix = match nodes[node].parent {
+            Some(parent) => Self::world_matrix(nodes, world, parent) * local,
+            None => local,
+        };
+        world[node] = Some(matrix);
+        matrix
     }
 }
 
@@ -266,52 +1862,275 @@ impl core::fmt::Display for Scene {
             )?;
             writeln!(f, "{}", mesh.transform,)?;
         }
+        for animation in self.animations.iter() {
+            writeln!(
+                f,
+                "animation \"{}\" - {} channels, {}s",
+                animation.name.as_deref().unwrap_or(""),
+                animation.channels.len(),
+                animation.duration
+            )?;
+        }
         Ok(())
     }
 }
 
+// KHR_texture_transform as a matrix applied to UVs: translation * rotation * scale.
+// the rotation is counter-clockwise in UV space, whose v axis points down.
+fn texture_transform_matrix(transform: &gltf::texture::TextureTransform) -> Mat3 {
+    let (sin, cos) = transform.rotation().sin_cos();
+    let rotation = Mat3::from_cols(Vec3::new(cos, -sin, 0.0), Vec3::new(sin, cos, 0.0), Vec3::Z);
+    Mat3::from_translation(transform.offset().into())
+        * rotation
+        * Mat3::from_scale(transform.scale().into())
+}
+
+// `indices` of a primitive drawn with `mode`, as a list of the returned topology.
+// strips and fans keep the winding of their first triangle.
+fn unroll_indices(mode: gltf::mesh::Mode, indices: Vec<u32>) -> (Topology, Vec<u32>) {
+    use gltf::mesh::Mode;
+    match mode {
+        Mode::Points => (Topology::Points, indices),
+        Mode::Lines => (Topology::Lines, indices),
+        Mode::LineStrip => (
+            Topology::Lines,
+            indices.windows(2).flatten().copied().collect(),
+        ),
+        Mode::LineLoop => {
+            let closing = match indices.as_slice() {
+                [first, .., last] => vec![*last, *first],
+                _ => Vec::new(),
+            };
+            let mut lines: Vec<u32> = indices.windows(2).flatten().copied().collect();
+            lines.extend(closing);
+            (Topology::Lines, lines)
+        }
+        Mode::Triangles => (Topology::Triangles, indices),
+        Mode::TriangleStrip => (
+            Topology::Triangles,
+            indices
+                .windows(3)
+                .enumerate()
+                .flat_map(|(i, triangle)| {
+                    if i % 2 == 0 {
+                        [triangle[0], triangle[1], triangle[2]]
+                    } else {
+                        [triangle[0], triangle[2], triangle[1]]
+                    }
+                })
+                .collect(),
+        ),
+        Mode::TriangleFan => (
+            Topology::Triangles,
+            indices
+                .get(1..)
+                .unwrap_or_default()
+                .windows(2)
+                .flat_map(|edge| [edge[0], edge[1], indices[0]])
+                .collect(),
+        ),
+    }
+}
+
+// calls `f` on every item, spread over rayon's thread pool with the `rayon` feature.
+fn for_each_parallel<T: Send>(items: Vec<T>, f: impl Fn(T) + Send + Sync) {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        items.into_par_iter().for_each(f);
+    }
+    #[cfg(not(feature = "rayon"))]
+    items.into_iter().for_each(f);
+}
+
 #[derive(Default)]
 struct Visitor {
     camera: Option<Camera>,
     lights: Vec<Light>,
     meshes: Vec<Mesh>,
+    nodes: Vec<Node>,
+    skins: Vec<Skin>,
+    animations: Vec<Animation>,
+    // keyed by glTF image index.
+    images: ImageCache<usize>,
+    // whether the normals and tangents of each primitive of each mesh are left to generate.
+    missing_vertex_data: Vec<Vec<(bool, bool)>>,
 }
 
 impl Visitor {
-    pub fn visit(gltf: gltf::Gltf) -> Self {
-        let mut buffer_data = Vec::new();
-        for buffer in gltf.buffers() {
-            match buffer.source() {
-                gltf::buffer::Source::Bin => {
-                    if let Some(blob) = gltf.blob.as_deref() {
-                        buffer_data.push(blob);
-                    };
-                }
-                gltf::buffer::Source::Uri(_) => todo!(),
+    // `base` is the directory that relative buffer URIs are resolved against.
+    // only the nodes of one scene are placed, `scene_index` or the default one.
+    pub fn visit(
+        gltf: gltf::Gltf,
+        base: Option<&std::path::Path>,
+        scene_index: Option<usize>,
+    ) -> Result<Self, SceneError> {
+        let gltf::Gltf { document, blob } = gltf;
+        let scene = match scene_index {
+            Some(index) => Some(
+                document
+                    .scenes()
+                    .nth(index)
+                    .ok_or(SceneError::MissingScene(index))?,
+            ),
+            None => document
+                .default_scene()
+                .or_else(|| document.scenes().next()),
+        };
+
+        // embedded binary chunk, external files, and base64 data URIs,
+        // in the index order the accessors refer to.
+        let buffer_data = gltf::import_buffers(&document, base, blob)?;
+
+        let mut nodes: Vec<Node> = document
+            .nodes()
+            .map(|node| Node {
+                parent: None,
+                transform: node.transform().into(),
+            })
+            .collect();
+        for node in document.nodes() {
+            for child in node.children() {
+                nodes[child.index()].parent = Some(node.index());
             }
         }
+        let skins = document
+            .skins()
+            .map(|skin| {
+                let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+                let reader = skin.reader(|buffer| Some(&buffer_data[buffer.index()]));
+                // absent inverse bind matrices are identity.
+                let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+                    Some(matrices) => matrices.map(|m| Mat4::from_cols_array_2d(&m)).collect(),
+                    None => vec![Mat4::IDENTITY; joints.len()],
+                };
+                Skin {
+                    joints,
+                    inverse_bind_matrices,
+                    joint_matrices: Vec::new(),
+                }
+            })
+            .collect();
+
+        let animations = document
+            .animations()
+            .map(|animation| Self::visit_animation(&buffer_data, &animation))
+            .collect();
+        let mut visitor = Self {
+            nodes,
+            skins,
+            animations,
+            ..Default::default()
+        };
+        // a file without scenes has nothing to show.
+        for node in scene.iter().flat_map(|scene| scene.nodes()) {
+            visitor.do_visit(&document, base, &buffer_data, &node, Mat4::IDENTITY)?;
+        }
+        visitor.generate_missing_vertex_data();
+
+        Ok(visitor)
+    }
 
-        let mut visitor = Self::default();
-        for scene in gltf.scenes() {
-            for node in scene.nodes() {
-                visitor.do_visit(&buffer_data, &node);
+    // generates the normals and tangents the file left out, for every primitive at once.
+    fn generate_missing_vertex_data(&mut self) {
+        let primitives: Vec<_> = self
+            .meshes
+            .iter_mut()
+            .zip(&self.missing_vertex_data)
+            .flat_map(|(mesh, missing)| mesh.primitives.iter_mut().zip(missing.iter().copied()))
+            .filter(|(_, (normals, tangents))| *normals || *tangents)
+            .collect();
+        for_each_parallel(primitives, |(primitive, (normals, tangents))| {
+            if normals {
+                primitive.generate_normals();
+            }
+            if tangents {
+                primitive.generate_tangents();
             }
+        });
+    }
+
+    // morph target weight channels are skipped.
+    fn visit_animation(
+        buffer_data: &[gltf::buffer::Data],
+        animation: &gltf::Animation,
+    ) -> Animation {
+        let mut channels = Vec::new();
+        for channel in animation.channels() {
+            let reader = channel.reader(|buffer| Some(&buffer_data[buffer.index()]));
+            let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+                continue;
+            };
+            let (path, keyframes): (AnimationPath, Vec<Vec4>) = match outputs {
+                gltf::animation::util::ReadOutputs::Translations(translations) => (
+                    AnimationPath::Translation,
+                    translations.map(|t| Vec3::from(t).extend(0.0)).collect(),
+                ),
+                gltf::animation::util::ReadOutputs::Rotations(rotations) => (
+                    AnimationPath::Rotation,
+                    rotations.into_f32().map(Vec4::from).collect(),
+                ),
+                gltf::animation::util::ReadOutputs::Scales(scales) => (
+                    AnimationPath::Scale,
+                    scales.map(|s| Vec3::from(s).extend(0.0)).collect(),
+                ),
+                gltf::animation::util::ReadOutputs::MorphTargetWeights(_) => continue,
+            };
+            let times: Vec<f32> = times.collect();
+            if times.is_empty() {
+                continue;
+            }
+            channels.push(AnimationChannel {
+                node: channel.target().node().index(),
+                path,
+                interpolation: match channel.sampler().interpolation() {
+                    gltf::animation::Interpolation::Step => Interpolation::Step,
+                    gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                    gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                },
+                times,
+                keyframes,
+            });
         }
 
-        visitor
+        Animation {
+            name: animation.name().map(|name| name.to_owned()),
+            duration: channels
+                .iter()
+                .filter_map(|channel| channel.times.last().copied())
+                .fold(0.0, f32::max),
+            channels,
+        }
     }
 
-    fn do_visit(&mut self, buffer_data: &[&[u8]], node: &gltf::Node) {
-        let transform: Transform = node.transform().into();
+    fn do_visit(
+        &mut self,
+        document: &gltf::Document,
+        base: Option<&std::path::Path>,
+        buffer_data: &[gltf::buffer::Data],
+        node: &gltf::Node,
+        parent: Mat4,
+    ) -> Result<(), SceneError> {
+        // world space, everything below is placed by its ancestors too.
+        let matrix = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+        let transform: Transform = matrix.into();
 
         if let Some(mesh) = node.mesh() {
             let mut primitives = Vec::new();
+            let mut missing_vertex_data = Vec::new();
             for primitive in mesh.primitives() {
-                let reader = primitive.reader(|buffer| Some(buffer_data[buffer.index()]));
-                let positions = reader.read_positions().unwrap();
-                let normals = reader.read_normals().unwrap();
-                let indices = reader.read_indices().unwrap();
-                assert_eq!(positions.len(), normals.len());
+                // the reader applies sparse accessors' substitutions, over zeros for accessors
+                // without a buffer view. bounds are computed from the result, not the accessor's min and max.
+                let reader = primitive.reader(|buffer| Some(&buffer_data[buffer.index()]));
+                let positions = reader
+                    .read_positions()
+                    .ok_or(SceneError::MissingAttribute("POSITION"))?;
+                // u8 and u16 indices are widened. non-indexed primitives draw their vertices in order.
+                let indices: Vec<u32> = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..positions.len() as u32).collect(),
+                };
+                let (topology, indices) = unroll_indices(primitive.mode(), indices);
 
                 let i_material = primitive.material();
 
@@ -319,55 +2138,239 @@ impl Visitor {
                 let base_color = pbr_metallic_roughness.base_color_factor();
                 let metallic = pbr_metallic_roughness.metallic_factor();
                 let roughness = pbr_metallic_roughness.roughness_factor();
-                assert!(
-                    i_material
-                        .pbr_metallic_roughness()
-                        .metallic_roughness_texture()
-                        .is_none()
-                );
-                let material = Material {
+                let normal_texture = i_material.normal_texture();
+                let alpha_mode: AlphaMode = i_material.alpha_mode().into();
+                // the gltf crate doesn't parse KHR_materials_clearcoat, so its JSON is read as is.
+                let clearcoat = i_material.extension_value("KHR_materials_clearcoat");
+                let clearcoat_value = |key: &str| {
+                    clearcoat
+                        .and_then(|clearcoat| clearcoat.get(key))
+                        .and_then(|value| value.as_f64())
+                        .map(|value| value as f32)
+                };
+                let clearcoat_factor = clearcoat_value("clearcoatFactor").unwrap_or(0.0);
+                let clearcoat_normal =
+                    clearcoat.and_then(|clearcoat| clearcoat.get("clearcoatNormalTexture"));
+                let transmission = i_material.transmission();
+                let transmission_factor = transmission
+                    .as_ref()
+                    .map_or(0.0, |transmission| transmission.transmission_factor());
+                let mut material = Material {
                     base_color: base_color.into(),
                     metallic,
                     roughness,
+                    normal_scale: normal_texture.as_ref().map_or(1.0, |info| info.scale()),
+                    alpha_mode: alpha_mode as u32,
+                    alpha_cutoff: i_material.alpha_cutoff().unwrap_or(0.5),
+                    occlusion_strength: i_material
+                        .occlusion_texture()
+                        .map_or(1.0, |info| info.strength()),
+                    flags: if i_material.unlit() {
+                        Material::UNLIT
+                    } else {
+                        0
+                    } | if clearcoat_factor > 0.0 {
+                        Material::CLEARCOAT
+                    } else {
+                        0
+                    } | if transmission_factor > 0.0 {
+                        Material::TRANSMISSION
+                    } else {
+                        0
+                    },
+                    emissive: Vec3::from(i_material.emissive_factor())
+                        * i_material.emissive_strength().unwrap_or(1.0),
+                    clearcoat: clearcoat_factor,
+                    clearcoat_roughness: clearcoat_value("clearcoatRoughnessFactor").unwrap_or(0.0),
+                    clearcoat_normal_scale: clearcoat_normal
+                        .and_then(|info| info.get("scale"))
+                        .and_then(|scale| scale.as_f64())
+                        .map_or(1.0, |scale| scale as f32),
+                    transmission: transmission_factor,
+                    ..Default::default()
+                };
+                // one transform for every texture of the material. normal and occlusion textures
+                // don't expose theirs, so it's the base color texture's, or else the metallic-roughness one's.
+                if let Some(transform) = pbr_metallic_roughness
+                    .base_color_texture()
+                    .and_then(|info| info.texture_transform())
+                    .or_else(|| {
+                        pbr_metallic_roughness
+                            .metallic_roughness_texture()
+                            .and_then(|info| info.texture_transform())
+                    })
+                {
+                    material.set_uv_transform(texture_transform_matrix(&transform));
+                }
+                let mut load_texture = |texture: Option<gltf::Texture>| {
+                    texture
+                        .map(|texture| {
+                            let source = texture.source();
+                            let image = self.images.get_or_load(source.index(), || {
+                                let data = gltf::image::Data::from_source(
+                                    source.source(),
+                                    base,
+                                    buffer_data,
+                                )?;
+                                Image::from_gltf(data)
+                            });
+                            image.map(|image| TextureRef {
+                                image,
+                                sampler: texture.sampler().into(),
+                            })
+                        })
+                        .transpose()
+                };
+                let base_color_texture = load_texture(
+                    pbr_metallic_roughness
+                        .base_color_texture()
+                        .map(|info| info.texture()),
+                )?;
+                let normal_texture = load_texture(normal_texture.map(|info| info.texture()))?;
+                let metallic_roughness_texture = load_texture(
+                    pbr_metallic_roughness
+                        .metallic_roughness_texture()
+                        .map(|info| info.texture()),
+                )?;
+                let occlusion_texture =
+                    load_texture(i_material.occlusion_texture().map(|info| info.texture()))?;
+                let emissive_texture =
+                    load_texture(i_material.emissive_texture().map(|info| info.texture()))?;
+                let clearcoat_normal_texture = load_texture(
+                    clearcoat_normal
+                        .and_then(|info| info.get("index"))
+                        .and_then(|index| index.as_u64())
+                        .and_then(|index| document.textures().nth(index as usize)),
+                )?;
+                let transmission_texture = load_texture(
+                    transmission
+                        .and_then(|transmission| transmission.transmission_texture())
+                        .map(|info| info.texture()),
+                )?;
+
+                // generated once the primitive is built when the file has none.
+                let read_normals = reader.read_normals();
+                let has_normals = read_normals.is_some();
+                let normals: Vec<Vec3> = match read_normals {
+                    Some(normals) => normals.map(Vec3::from).collect(),
+                    None => vec![Vec3::ZERO; positions.len()],
+                };
+
+                let uvs: Vec<Vec2> = match reader.read_tex_coords(0) {
+                    Some(uvs) => uvs.into_f32().map(Vec2::from).collect(),
+                    None => vec![Vec2::ZERO; positions.len()],
+                };
+
+                let colors: Vec<Vec4> = match reader.read_colors(0) {
+                    Some(colors) => colors.into_rgba_f32().map(Vec4::from).collect(),
+                    None => vec![Vec4::ONE; positions.len()],
+                };
+
+                // unskinned vertices have zero weights.
+                let joints: Vec<[u32; 4]> = match reader.read_joints(0) {
+                    Some(joints) => joints.into_u16().map(|j| j.map(u32::from)).collect(),
+                    None => vec![[0; 4]; positions.len()],
+                };
+                let weights: Vec<Vec4> = match reader.read_weights(0) {
+                    Some(weights) => weights.into_f32().map(Vec4::from).collect(),
+                    None => vec![Vec4::ZERO; positions.len()],
                 };
 
-                primitives.push(Primitive {
-                    vertices: positions
-                        .zip(normals)
-                        .map(|(position, normal)| crate::renderer::Vertex {
+                let tangents = reader.read_tangents();
+
+                let vertices: Vec<crate::renderer::Vertex> = positions
+                    .zip(normals)
+                    .zip(uvs)
+                    .zip(colors)
+                    .zip(joints)
+                    .zip(weights)
+                    .map(|(((((position, normal), uv), color), joints), weights)| {
+                        crate::renderer::Vertex {
                             position: position.into(),
-                            normal: normal.into(),
-                        })
-                        .collect(),
-                    indices: indices.into_u32().collect(),
+                            normal,
+                            uv,
+                            tangent: Vec4::ZERO,
+                            color,
+                            joints,
+                            weights,
+                        }
+                    })
+                    .collect();
+                let (aabb_min, aabb_max) = Primitive::compute_aabb(&vertices);
+                let mut primitive = Primitive {
+                    vertices,
+                    indices,
+                    topology,
                     material,
-                })
+                    base_color_texture,
+                    normal_texture,
+                    metallic_roughness_texture,
+                    occlusion_texture,
+                    emissive_texture,
+                    clearcoat_normal_texture,
+                    transmission_texture,
+                    alpha_mode,
+                    double_sided: i_material.double_sided(),
+                    material_index: i_material.index(),
+                    aabb_min,
+                    aabb_max,
+                };
+                // generated once every node is visited, see `generate_missing_vertex_data`.
+                let generate_tangents = match tangents {
+                    Some(tangents) => {
+                        for (vertex, tangent) in primitive.vertices.iter_mut().zip(tangents) {
+                            vertex.tangent = tangent.into();
+                        }
+                        false
+                    }
+                    None => topology == Topology::Triangles,
+                };
+                missing_vertex_data.push((!has_normals, generate_tangents));
+                primitives.push(primitive);
             }
+            self.missing_vertex_data.push(missing_vertex_data);
             self.meshes.push(Mesh {
                 name: node.name().map(|a| a.to_owned()),
-                transform: node.transform().into(),
+                transform: transform.clone(),
                 primitives,
+                mesh_index: mesh.index(),
+                node: node.index(),
+                skin: node.skin().map(|skin| skin.index()),
             });
         }
 
         if let Some(camera) = node.camera() {
+            let (yaw, pitch, roll) = Camera::yaw_pitch_roll(transform.rotation);
             self.camera = match camera.projection() {
-                gltf::camera::Projection::Orthographic(_orthographic) => {
-                    todo!("Orthographic camera")
-                }
-                gltf::camera::Projection::Perspective(perspective) => {
-                    let (yaw, pitch, roll) = Camera::yaw_pitch_roll(transform.rotation);
-                    Some(Camera {
-                        position: transform.translation,
-                        yaw,
-                        pitch,
-                        roll,
-
-                        yfov: perspective.yfov(),
-                        zfar: perspective.zfar(),
-                        znear: perspective.znear(),
-                    })
-                }
+                gltf::camera::Projection::Orthographic(orthographic) => Some(Camera {
+                    position: transform.translation,
+                    yaw,
+                    pitch,
+                    roll,
+
+                    projection: Projection::Orthographic {
+                        xmag: orthographic.xmag(),
+                        ymag: orthographic.ymag(),
+                    },
+                    yfov: 1.0,
+                    zfar: Some(orthographic.zfar()),
+                    znear: orthographic.znear(),
+                    mode: CameraMode::Normal,
+                    top_down_height: 20.0,
+                }),
+                gltf::camera::Projection::Perspective(perspective) => Some(Camera {
+                    position: transform.translation,
+                    yaw,
+                    pitch,
+                    roll,
+
+                    projection: Projection::Perspective,
+                    yfov: perspective.yfov(),
+                    zfar: perspective.zfar(),
+                    znear: perspective.znear(),
+                    mode: CameraMode::Normal,
+                    top_down_height: 20.0,
+                }),
             }
         }
 
@@ -402,7 +2405,312 @@ impl Visitor {
         }
 
         for child in node.children() {
-            self.do_visit(buffer_data, &child);
+            self.do_visit(document, base, buffer_data, &child, matrix)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a binary glTF with `json` as its JSON chunk and `bin` as its embedded buffer.
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = bin.to_vec();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut bytes = Vec::with_capacity(length);
+        bytes.extend_from_slice(b"glTF");
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"JSON");
+        bytes.extend_from_slice(&json);
+        bytes.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"BIN\0");
+        bytes.extend_from_slice(&bin);
+        bytes
+    }
+
+    fn mesh<'a>(scene: &'a Scene, name: &str) -> &'a Mesh {
+        scene
+            .meshes
+            .iter()
+            .find(|mesh| mesh.name.as_deref() == Some(name))
+            .unwrap()
+    }
+
+    fn primitive<'a>(scene: &'a Scene, name: &str) -> &'a Primitive {
+        &mesh(scene, name).primitives[0]
+    }
+
+    fn positions(scene: &Scene, name: &str) -> Vec<Vec3> {
+        primitive(scene, name)
+            .vertices
+            .iter()
+            .map(|vertex| vertex.position)
+            .collect()
+    }
+
+    #[test]
+    fn index_types() {
+        let mut bin: Vec<u8> = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+        bin.extend_from_slice(&[2, 1, 0, 0]);
+        for index in [0u16, 2, 1] {
+            bin.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 46}],
+            "bufferViews": [
+                {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+                {"buffer": 0, "byteOffset": 36, "byteLength": 3},
+                {"buffer": 0, "byteOffset": 40, "byteLength": 6}
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0, 0, 0], "max": [1, 1, 0]
+                },
+                {"bufferView": 1, "componentType": 5121, "count": 3, "type": "SCALAR"},
+                {"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}
+            ],
+            "meshes": [
+                {"primitives": [{"attributes": {"POSITION": 0}, "indices": 1}]},
+                {"primitives": [{"attributes": {"POSITION": 0}, "indices": 2}]},
+                {"primitives": [{"attributes": {"POSITION": 0}}]}
+            ],
+            "nodes": [
+                {"name": "u8", "mesh": 0},
+                {"name": "u16", "mesh": 1},
+                {"name": "non-indexed", "mesh": 2}
+            ],
+            "scenes": [{"nodes": [0, 1, 2]}],
+            "scene": 0
+        }"#;
+        let scene = Scene::from_glb_bytes(&glb(json, &bin)).unwrap();
+
+        assert_eq!(primitive(&scene, "u8").indices, [2, 1, 0]);
+        assert_eq!(primitive(&scene, "u16").indices, [0, 2, 1]);
+        assert_eq!(primitive(&scene, "non-indexed").indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn sparse_positions() {
+        let mut bin: Vec<u8> = Vec::new();
+        let mut push = |values: &[f32]| {
+            for value in values {
+                bin.extend_from_slice(&value.to_le_bytes());
+            }
+        };
+        // base positions of the first accessor, then the substituted ones of both.
+        push(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        push(&[5.0, 5.0, 5.0]);
+        push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        // u16 index 1 for the first accessor, u8 indices 0 and 2 for the second.
+        bin.extend_from_slice(&1u16.to_le_bytes());
+        bin.extend_from_slice(&[0, 2]);
+
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 76}],
+            "bufferViews": [
+                {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+                {"buffer": 0, "byteOffset": 36, "byteLength": 12},
+                {"buffer": 0, "byteOffset": 48, "byteLength": 24},
+                {"buffer": 0, "byteOffset": 72, "byteLength": 2},
+                {"buffer": 0, "byteOffset": 74, "byteLength": 2}
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0, 0, 0], "max": [5, 5, 5],
+                    "sparse": {
+                        "count": 1,
+                        "indices": {"bufferView": 3, "componentType": 5123},
+                        "values": {"bufferView": 1}
+                    }
+                },
+                {
+                    "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0, 0, 0], "max": [4, 5, 6],
+                    "sparse": {
+                        "count": 2,
+                        "indices": {"bufferView": 4, "componentType": 5121},
+                        "values": {"bufferView": 2}
+                    }
+                }
+            ],
+            "meshes": [
+                {"primitives": [{"attributes": {"POSITION": 0}}]},
+                {"primitives": [{"attributes": {"POSITION": 1}}]}
+            ],
+            "nodes": [
+                {"name": "with view", "mesh": 0},
+                {"name": "without view", "mesh": 1}
+            ],
+            "scenes": [{"nodes": [0, 1]}],
+            "scene": 0
+        }"#;
+        let scene = Scene::from_glb_bytes(&glb(json, &bin)).unwrap();
+
+        assert_eq!(
+            positions(&scene, "with view"),
+            [Vec3::ZERO, Vec3::splat(5.0), Vec3::Y]
+        );
+        assert_eq!(
+            positions(&scene, "without view"),
+            [
+                Vec3::new(1.0, 2.0, 3.0),
+                Vec3::ZERO,
+                Vec3::new(4.0, 5.0, 6.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_nodes() {
+        let mut bin: Vec<u8> = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // a child at its translated parent's origin, and one off the origin of a rotated parent.
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "buffers": [{"byteLength": 36}],
+            "bufferViews": [{"buffer": 0, "byteOffset": 0, "byteLength": 36}],
+            "accessors": [
+                {
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0, 0, 0], "max": [1, 1, 0]
+                }
+            ],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+            "nodes": [
+                {"name": "translated", "translation": [1, 2, 3], "children": [1]},
+                {"name": "at origin", "mesh": 0},
+                {
+                    "name": "rotated", "translation": [0, 0, 5],
+                    "rotation": [0, 0.70710677, 0, 0.70710677], "children": [3]
+                },
+                {"name": "off origin", "mesh": 0, "translation": [1, 0, 0]}
+            ],
+            "scenes": [{"nodes": [0, 2]}],
+            "scene": 0
+        }"#;
+        let scene = Scene::from_glb_bytes(&glb(json, &bin)).unwrap();
+
+        let at_origin = &mesh(&scene, "at origin").transform;
+        assert!(
+            at_origin
+                .translation
+                .abs_diff_eq(Vec3::new(1.0, 2.0, 3.0), 1e-6)
+        );
+        assert!(at_origin.rotation.abs_diff_eq(Quat::IDENTITY, 1e-6));
+
+        // +x turned a quarter around +y is -z.
+        let off_origin = &mesh(&scene, "off origin").transform;
+        assert!(
+            off_origin
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 0.0, 4.0), 1e-6)
+        );
+        assert!(
+            off_origin
+                .rotation
+                .abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2), 1e-6)
+        );
+    }
+
+    #[test]
+    fn transform_lerp() {
+        let a = Transform {
+            translation: Vec3::new(1.0, 2.0, 3.0),
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+        };
+        let b = Transform {
+            translation: Vec3::new(-3.0, 0.0, 5.0),
+            rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::splat(3.0),
+        };
+
+        let start = a.lerp(&b, 0.0);
+        assert_eq!(start.translation, a.translation);
+        assert!(start.rotation.abs_diff_eq(a.rotation, 1e-6));
+        assert_eq!(start.scale, a.scale);
+
+        let end = a.lerp(&b, 1.0);
+        assert!(end.translation.abs_diff_eq(b.translation, 1e-6));
+        assert!(end.rotation.abs_diff_eq(b.rotation, 1e-6));
+        assert!(end.scale.abs_diff_eq(b.scale, 1e-6));
+
+        // halfway along the arc, not a normalized average of the quaternions' components.
+        let middle = a.lerp(&b, 0.5);
+        assert!(
+            middle
+                .translation
+                .abs_diff_eq(Vec3::new(-1.0, 1.0, 4.0), 1e-6)
+        );
+        assert!(
+            middle
+                .rotation
+                .abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4), 1e-6)
+        );
+        assert!(middle.scale.abs_diff_eq(Vec3::splat(2.0), 1e-6));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn scene_json_round_trip() {
+        let mut scene = Scene::new();
+        let mut cube = Mesh::cube();
+        cube.transform.translation = Vec3::new(1.0, 2.0, 3.0);
+        cube.primitives[0].material.metallic = 0.25;
+        cube.primitives[0].material.roughness = 0.75;
+        scene.add_mesh(cube);
+        scene.add_light(Light::Point {
+            pos: Vec3::new(0.0, 4.0, 0.0),
+            color: Vec3::new(1.0, 0.5, 0.25),
+            radiant_flux: 20.0,
+        });
+        scene.camera.position = Vec3::new(0.0, 1.0, 5.0);
+        scene.camera.yaw = 0.5;
+
+        let path = std::env::temp_dir().join(format!("pbrpbr-scene-{}.json", std::process::id()));
+        scene.save(&path).unwrap();
+        let loaded = Scene::from_json(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.camera, scene.camera);
+        assert_eq!(loaded.lights, scene.lights);
+        assert_eq!(loaded.meshes.len(), scene.meshes.len());
+        for (loaded, mesh) in loaded.meshes.iter().zip(scene.meshes.iter()) {
+            assert_eq!(loaded.name, mesh.name);
+            assert_eq!(loaded.transform, mesh.transform);
+            assert_eq!(loaded.primitives.len(), mesh.primitives.len());
+            for (loaded, primitive) in loaded.primitives.iter().zip(mesh.primitives.iter()) {
+                assert_eq!(
+                    bytemuck::bytes_of(&loaded.material),
+                    bytemuck::bytes_of(&primitive.material)
+                );
+                assert_eq!(
+                    bytemuck::cast_slice::<_, u8>(&loaded.vertices),
+                    bytemuck::cast_slice::<_, u8>(&primitive.vertices)
+                );
+                assert_eq!(loaded.indices, primitive.indices);
+            }
         }
     }
 }
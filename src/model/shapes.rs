@@ -0,0 +1,146 @@
+// primitives with the default material, centered at the origin.
+// triangles are counter-clockwise seen from outside, so back-face culling keeps the outer faces.
+use super::Primitive;
+use crate::renderer::Vertex;
+use glam::{Vec2, Vec3, Vec4};
+
+// `size` long sides, each face with its own vertices and the whole texture.
+pub fn cube(size: f32) -> Primitive {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for normal in [Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z] {
+        add_quad(&mut vertices, &mut indices, normal, Vec3::Y, size);
+    }
+    add_quad(&mut vertices, &mut indices, Vec3::Y, Vec3::NEG_Z, size);
+    add_quad(&mut vertices, &mut indices, Vec3::NEG_Y, Vec3::Z, size);
+    Primitive::new(vertices, indices)
+}
+
+// diameter 1, with `rings` bands from pole to pole and `sectors` segments around the y axis.
+// the texture wraps around once, its top at the +y pole.
+pub fn uv_sphere(rings: u32, sectors: u32) -> Primitive {
+    let rings = rings.max(2);
+    let sectors = sectors.max(3);
+    let mut vertices = Vec::new();
+    for ring in 0..=rings {
+        let theta = std::f32::consts::PI * ring as f32 / rings as f32;
+        for sector in 0..=sectors {
+            let normal = around_y(sector, sectors) * theta.sin() + Vec3::Y * theta.cos();
+            let uv = Vec2::new(sector as f32 / sectors as f32, ring as f32 / rings as f32);
+            vertices.push(vertex(normal * 0.5, normal, uv));
+        }
+    }
+
+    let mut indices = Vec::new();
+    for ring in 0..rings {
+        for sector in 0..sectors {
+            let top = ring * (sectors + 1) + sector;
+            let bottom = top + sectors + 1;
+            // the triangles touching a pole would be degenerate.
+            if ring > 0 {
+                indices.extend([top, bottom, top + 1]);
+            }
+            if ring < rings - 1 {
+                indices.extend([top + 1, bottom, bottom + 1]);
+            }
+        }
+    }
+    Primitive::new(vertices, indices)
+}
+
+// side length 1 in the xz plane facing +y, split into `subdivisions` x `subdivisions` squares.
+pub fn plane(subdivisions: u32) -> Primitive {
+    let subdivisions = subdivisions.max(1);
+    let mut vertices = Vec::new();
+    for row in 0..=subdivisions {
+        for column in 0..=subdivisions {
+            let uv = Vec2::new(column as f32, row as f32) / subdivisions as f32;
+            let position = Vec3::new(uv.x - 0.5, 0.0, uv.y - 0.5);
+            vertices.push(vertex(position, Vec3::Y, uv));
+        }
+    }
+
+    let mut indices = Vec::new();
+    for row in 0..subdivisions {
+        for column in 0..subdivisions {
+            let top_left = row * (subdivisions + 1) + column;
+            let bottom_left = top_left + subdivisions + 1;
+            indices.extend([top_left, bottom_left, bottom_left + 1]);
+            indices.extend([top_left, bottom_left + 1, top_left + 1]);
+        }
+    }
+    Primitive::new(vertices, indices)
+}
+
+// diameter and height 1 around the y axis, with `segments` flat sides and capped ends.
+pub fn cylinder(segments: u32) -> Primitive {
+    let segments = segments.max(3);
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    // the texture wraps around the side once.
+    for (y, v) in [(0.5, 0.0), (-0.5, 1.0)] {
+        for segment in 0..=segments {
+            let normal = around_y(segment, segments);
+            let uv = Vec2::new(segment as f32 / segments as f32, v);
+            vertices.push(vertex(normal * 0.5 + Vec3::Y * y, normal, uv));
+        }
+    }
+    for segment in 0..segments {
+        let top = segment;
+        let bottom = top + segments + 1;
+        indices.extend([top, bottom, top + 1]);
+        indices.extend([top + 1, bottom, bottom + 1]);
+    }
+
+    // each cap is a fan around its center, mapped like the faces of `cube`.
+    for normal in [Vec3::Y, Vec3::NEG_Y] {
+        let center = vertices.len() as u32;
+        vertices.push(vertex(normal * 0.5, normal, Vec2::splat(0.5)));
+        for segment in 0..segments {
+            let offset = around_y(segment, segments) * 0.5;
+            let uv = Vec2::new(offset.x, offset.z * normal.y) + 0.5;
+            vertices.push(vertex(normal * 0.5 + offset, normal, uv));
+        }
+        for segment in 0..segments {
+            let current = center + 1 + segment;
+            let next = center + 1 + (segment + 1) % segments;
+            if normal.y > 0.0 {
+                indices.extend([center, current, next]);
+            } else {
+                indices.extend([center, next, current]);
+            }
+        }
+    }
+    Primitive::new(vertices, indices)
+}
+
+// unit vector in the xz plane, `step` of `steps` turns from +z towards +x.
+fn around_y(step: u32, steps: u32) -> Vec3 {
+    let angle = std::f32::consts::TAU * step as f32 / steps as f32;
+    Vec3::new(angle.sin(), 0.0, angle.cos())
+}
+
+// a `size` square facing `normal` at `size / 2` along it, with the top of its texture towards `up`.
+fn add_quad(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, normal: Vec3, up: Vec3, size: f32) {
+    // counter-clockwise seen from the front, since right x up = normal.
+    let right = up.cross(normal);
+    let first = vertices.len() as u32;
+    for (x, y) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+        let position = (normal * 0.5 + right * x + up * y) * size;
+        vertices.push(vertex(position, normal, Vec2::new(x + 0.5, 0.5 - y)));
+    }
+    indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+}
+
+fn vertex(position: Vec3, normal: Vec3, uv: Vec2) -> Vertex {
+    Vertex {
+        position,
+        normal,
+        uv,
+        tangent: Vec4::ZERO,
+        color: Vec4::ONE,
+        joints: [0; 4],
+        weights: Vec4::ZERO,
+    }
+}
@@ -1,16 +1,29 @@
-use glam::{Mat3, Mat4, Vec3};
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use std::collections::HashMap;
 use wgpu::BindGroupLayoutDescriptor;
 
 #[repr(C, packed)]
 #[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     pub position: Vec3,
     pub normal: Vec3,
+    pub uv: Vec2,
+    // xyz is the tangent, w is the bitangent sign.
+    pub tangent: Vec4,
+    // linear RGBA, multiplied into the base color.
+    pub color: Vec4,
+    // indices into the primitive's joint matrices, all weights are zero for unskinned vertices.
+    pub joints: [u32; 4],
+    pub weights: Vec4,
 }
 
 impl Vertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+    // locations 2..=8 are taken by `Instance`.
+    const ATTRIBS: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+        0 => Float32x3, 1 => Float32x3, 9 => Float32x2, 10 => Float32x4, 11 => Float32x4,
+        12 => Uint32x4, 13 => Float32x4
+    ];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
@@ -23,7 +36,7 @@ impl Vertex {
 
 #[repr(C, packed)]
 #[derive(Clone, Copy, Debug, bytemuck::Zeroable, bytemuck::Pod)]
-pub struct Instance {
+pub(crate) struct Instance {
     pub model: Mat4,
     pub rot: Mat3,
 }
@@ -47,6 +60,10 @@ pub(crate) struct VertexBuffer {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub instance_buffer: wgpu::Buffer,
+    // one `DrawIndexedIndirectArgs` per draw, in draw order.
+    pub indirect_buffer: wgpu::Buffer,
+    // the bytes last written to the instance buffer, so that only moved instances are written again.
+    uploaded_instances: Vec<u8>,
 }
 
 impl VertexBuffer {
@@ -72,57 +89,158 @@ impl VertexBuffer {
             mapped_at_creation: false,
         });
 
+        let indirect_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("indirect buffer"),
+            usage: wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            size: 0,
+            mapped_at_creation: false,
+        });
+
         Self {
             vertex_buffer,
             index_buffer,
             instance_buffer,
+            indirect_buffer,
+            uploaded_instances: Vec::new(),
         }
     }
 
-    pub fn write(
+    pub fn write_geometry(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         vertices: &[Vertex],
         indices: &[u32],
-        instances: &[Instance],
     ) {
-        if self.vertex_buffer.size() < vertices.len() as u64 {
-            self.vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("vertex buffer"),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                size: size_of_val(vertices) as u64,
-                mapped_at_creation: false,
-            });
+        Self::upload(
+            device,
+            queue,
+            &mut self.vertex_buffer,
+            "vertex buffer",
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            bytemuck::cast_slice(vertices),
+        );
+        Self::upload(
+            device,
+            queue,
+            &mut self.index_buffer,
+            "index buffer",
+            wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            bytemuck::cast_slice(indices),
+        );
+    }
+
+    // writes only the runs of instances that differ from the last upload.
+    // returns how many instances were written.
+    pub fn write_instances(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &[Instance],
+        draws: &[wgpu::util::DrawIndexedIndirectArgs],
+    ) -> u32 {
+        Self::upload(
+            device,
+            queue,
+            &mut self.indirect_buffer,
+            "indirect buffer",
+            wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+            bytemuck::cast_slice(draws),
+        );
+
+        let data: &[u8] = bytemuck::cast_slice(instances);
+        if Self::reserve(
+            device,
+            &mut self.instance_buffer,
+            "instance buffer",
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            data.len() as u64,
+        ) || self.uploaded_instances.len() != data.len()
+        {
+            queue.write_buffer(&self.instance_buffer, 0, data);
+            self.uploaded_instances = data.to_vec();
+            return instances.len() as u32;
         }
-        if self.index_buffer.size() < indices.len() as u64 {
-            self.index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("index buffer"),
-                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                size: size_of_val(indices) as u64,
-                mapped_at_creation: false,
-            });
+
+        let stride = size_of::<Instance>();
+        let changed: Vec<bool> = data
+            .chunks(stride)
+            .zip(self.uploaded_instances.chunks(stride))
+            .map(|(instance, uploaded)| instance != uploaded)
+            .collect();
+        let mut written = 0;
+        let mut start = 0;
+        for run in changed.chunk_by(|a, b| a == b) {
+            let range = start * stride..(start + run.len()) * stride;
+            if run[0] {
+                queue.write_buffer(
+                    &self.instance_buffer,
+                    range.start as u64,
+                    &data[range.clone()],
+                );
+                self.uploaded_instances[range.clone()].copy_from_slice(&data[range]);
+                written += run.len() as u32;
+            }
+            start += run.len();
         }
-        if self.instance_buffer.size() < instances.len() as u64 {
-            self.instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("instance buffer"),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                size: size_of_val(instances) as u64,
+        written
+    }
+
+    fn upload(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer: &mut wgpu::Buffer,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        data: &[u8],
+    ) {
+        Self::reserve(device, buffer, label, usage, data.len() as u64);
+        queue.write_buffer(buffer, 0, data);
+    }
+
+    // reallocate `buffer` if it is smaller than `size` bytes, returns whether it did.
+    // grows to the next power of two so a growing scene doesn't reallocate every frame.
+    fn reserve(
+        device: &wgpu::Device,
+        buffer: &mut wgpu::Buffer,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        size: u64,
+    ) -> bool {
+        let grown = grown_size(buffer.size(), size);
+        if grown != buffer.size() {
+            *buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                usage,
+                size: grown,
                 mapped_at_creation: false,
             });
+            return true;
         }
-        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
-        queue.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(indices));
-        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        false
     }
 
     pub fn set(&self, render_pass: &mut wgpu::RenderPass) {
+        // nothing was uploaded yet, e.g. while the first scene loads. there's nothing to draw either.
+        if self.vertex_buffer.size() == 0 {
+            return;
+        }
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
     }
 }
 
+// buffers are never shrunk, and grow to the next power of two so that
+// a slowly growing scene reallocates only a logarithmic number of times.
+fn grown_size(current: u64, needed: u64) -> u64 {
+    if current >= needed {
+        current
+    } else {
+        needed.next_power_of_two()
+    }
+}
+
 pub(crate) struct UniformGroup {
     sizes: Vec<u64>,
     pub bind_group_layout: wgpu::BindGroupLayout,
@@ -135,7 +253,9 @@ impl UniformGroup {
         let layout_entries: Vec<wgpu::BindGroupLayoutEntry> = (0..sizes.len())
             .map(|i| wgpu::BindGroupLayoutEntry {
                 binding: i as u32,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                visibility: wgpu::ShaderStages::VERTEX
+                    | wgpu::ShaderStages::FRAGMENT
+                    | wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -208,6 +328,39 @@ impl UniformGroup {
         }
     }
 
+    // write one binding of the bind group, leaving the others as they are.
+    pub(crate) fn write_binding(
+        &self,
+        queue: &wgpu::Queue,
+        bind_group_id: u64,
+        binding: usize,
+        data: &[u8],
+    ) {
+        queue.write_buffer(
+            &self.bind_groups[bind_group_id as usize].0[binding],
+            0,
+            data,
+        );
+    }
+
+    // like `write`, but copies through `staging_belt` as part of `command_encoder`, so the data is
+    // in place for the commands recorded after it rather than for the whole submission.
+    pub(crate) fn write_staged(
+        &self,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        command_encoder: &mut wgpu::CommandEncoder,
+        bind_group_id: u64,
+        data: &[&[u8]],
+    ) {
+        for (buffer, data_entry) in self.bind_groups[bind_group_id as usize].0.iter().zip(data) {
+            if let Some(size) = wgpu::BufferSize::new(data_entry.len() as u64) {
+                staging_belt
+                    .write_buffer(command_encoder, buffer, 0, size)
+                    .copy_from_slice(data_entry);
+            }
+        }
+    }
+
     pub(crate) fn set(
         &self,
         render_pass: &mut wgpu::RenderPass,
@@ -222,31 +375,325 @@ impl UniformGroup {
     }
 }
 
-pub(crate) struct Renderer {
-    render_pipeline: wgpu::RenderPipeline,
+// render pipeline variants selected per draw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    topology: crate::model::Topology,
+    transparent: bool,
+    // back faces are not culled.
+    double_sided: bool,
+    // opaque depth was laid down by the depth prepass, so it's only tested for equality.
+    depth_prepass: bool,
+}
+
+// which pipelines `Renderer::draw_batches` binds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OpaquePass {
+    // the SSAO G-buffer, its pipeline is bound by the caller.
+    GBuffer,
+    // the depth prepass of the lit pass.
+    Depth,
+    Lit,
+}
+
+// the pipelines built from shader.wgsl, see `Renderer::reload_shader`.
+struct ShaderPipelines {
+    render_pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+    render_pipeline_shadow_map: wgpu::RenderPipeline,
+    render_pipeline_point_shadow_map: wgpu::RenderPipeline,
+    render_pipeline_full: wgpu::RenderPipeline,
+    render_pipeline_prepass: wgpu::RenderPipeline,
+    render_pipelines_depth_prepass: [wgpu::RenderPipeline; 2],
+    render_pipeline_outline_mask: wgpu::RenderPipeline,
+    render_pipeline_outline: wgpu::RenderPipeline,
+}
+
+// textures bound per primitive after the material sampler.
+const MATERIAL_TEXTURE_COUNT: u32 = 7;
+
+// size of the joint matrix uniform bound per primitive. must match shader.wgsl
+pub(crate) const MAX_JOINTS: usize = 128;
+
+// only the first lights of a scene cast shadows, any others light without them.
+// must match shader.wgsl
+const MAX_SHADOWED_LIGHTS: u32 = 4;
+// shadow map layers reserved per light. the directional light uses one per cascade, others use the first.
+const CASCADE_COUNT: u32 = 3;
+const SHADOW_MAP_LAYERS: u32 = MAX_SHADOWED_LIGHTS * CASCADE_COUNT;
+const SHADOW_MAP_SIZE: u32 = 1024;
+// point lights get a cube map each, in a separate cube array.
+const POINT_SHADOW_MAP_LAYERS: u32 = MAX_SHADOWED_LIGHTS * 6;
+const POINT_SHADOW_MAP_SIZE: u32 = 512;
+// the scene uniforms of every shadow map and the camera fit in one chunk, the lights of big
+// scenes get one of their own.
+const STAGING_BELT_CHUNK_SIZE: u64 = 1 << 16;
+
+// the lit pass renders linear color into this, `ToneMapping` maps it to the output.
+// MSAA needs the sample count to be supported for it.
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// a background for scenes without an environment, in linear HDR color like the lit scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyGradient {
+    // seen looking straight down, blended towards `top` through the horizon.
+    pub bottom: Vec3,
+    pub top: Vec3,
+}
+
+// must match the constants in tone_map.wgsl
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMapping {
+    Reinhard = 0,
+    Aces = 1,
+}
+
+// what the output shows instead of the lit scene. must match the constants in shader.wgsl
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DebugView {
+    #[default]
+    Off = 0,
+    // the shadow map of the nth shadowed light, its nearest cascade for directional lights.
+    // point lights render into a cube map instead, which isn't shown.
+    ShadowMap0 = 1,
+    ShadowMap1 = 2,
+    ShadowMap2 = 3,
+    ShadowMap3 = 4,
+    // view depth, black at the camera and white at `shadow_distance`.
+    Depth = 5,
+    Normals = 6,
+    Albedo = 7,
+    Roughness = 8,
+    Metallic = 9,
+}
+
+impl DebugView {
+    pub const ALL: [DebugView; 10] = [
+        DebugView::Off,
+        DebugView::ShadowMap0,
+        DebugView::ShadowMap1,
+        DebugView::ShadowMap2,
+        DebugView::ShadowMap3,
+        DebugView::Depth,
+        DebugView::Normals,
+        DebugView::Albedo,
+        DebugView::Roughness,
+        DebugView::Metallic,
+    ];
+
+    // the shadowed light whose shadow map is shown.
+    fn shadow_map(self) -> Option<u32> {
+        match self {
+            DebugView::ShadowMap0 => Some(0),
+            DebugView::ShadowMap1 => Some(1),
+            DebugView::ShadowMap2 => Some(2),
+            DebugView::ShadowMap3 => Some(3),
+            _ => None,
+        }
+    }
+
+    // written by the lit pass in place of its color.
+    fn is_surface(self) -> bool {
+        self as u32 >= DebugView::Depth as u32
+    }
+}
+
+// what the last frame submitted, see `Renderer::stats`. passes other than the scene's
+// geometry, like the skybox and post-processing, aren't counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    // draw calls of scene geometry in every pass. a multi-draw call counts once.
+    pub draw_calls: u32,
+    // triangles of every instance drawn, once per pass they're drawn in.
+    pub triangles: u64,
+    // uploaded by the last `write_vertex`, none when the geometry didn't change.
+    pub vertices: u32,
+    // uploaded by the last `write_vertex`, only those that moved or were culled or uncovered.
+    pub instances: u32,
+    // instances outside the camera frustum, only drawn into the shadow maps.
+    pub culled_draws: u32,
+    // shadow maps rendered, counting each cascade and cube face.
+    pub shadow_passes: u32,
+}
+
+// passes the HDR color through for the surface debug views. must match tone_map.wgsl
+const TONE_MAPPING_NONE: u32 = 2;
+
+// rasterization bias for the shadow map passes, on top of `Renderer::shadow_bias`.
+// the point light pass writes its own depth, so this only affects the other lights.
+const SHADOW_DEPTH_BIAS: wgpu::DepthBiasState = wgpu::DepthBiasState {
+    constant: 2,
+    slope_scale: 2.0,
+    clamp: 0.0,
+};
+
+pub struct Renderer {
+    render_pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
     render_pipeline_shadow_map: wgpu::RenderPipeline,
+    render_pipeline_point_shadow_map: wgpu::RenderPipeline,
+    render_pipeline_full: wgpu::RenderPipeline,
+    render_pipeline_tone_map: wgpu::RenderPipeline,
+    render_pipeline_prepass: wgpu::RenderPipeline,
+    // indexed by `double_sided`.
+    render_pipelines_depth_prepass: [wgpu::RenderPipeline; 2],
+    // mark the highlighted mesh in `outline_stencil`, then draw the outline around it.
+    render_pipeline_outline_mask: wgpu::RenderPipeline,
+    render_pipeline_outline: wgpu::RenderPipeline,
 
-    pub depth_texture: crate::texture::Texture,
+    depth_texture: crate::texture::Texture,
+    // covered by the highlighted mesh, so its outline is only drawn outside of it.
+    outline_stencil: crate::texture::Texture,
+    // lit pass output, read by the tone mapping pass.
+    hdr_texture: crate::texture::Texture,
+    hdr_bind_group_layout: wgpu::BindGroupLayout,
+    hdr_bind_group: wgpu::BindGroup,
+    bloom: crate::bloom::Bloom,
+    // the scene behind transmissive primitives, sampled by the lit shader.
+    transmission: crate::transmission::Transmission,
+    skybox: crate::skybox::Skybox,
+    grid: crate::grid::Grid,
+    // set by `show_grid`.
+    grid_visible: bool,
+    light_gizmos: crate::light_gizmos::LightGizmos,
+    fxaa: crate::fxaa::Fxaa,
+    bloom_sampler: wgpu::Sampler,
+    // rendered to instead of `hdr_texture` and resolved into it. None without MSAA.
+    multisampled_texture: Option<crate::texture::Texture>,
+    sample_count: u32,
+    // format of the texture views rendered to.
+    output_format: wgpu::TextureFormat,
+    // shadow passes submit their draws with `multi_draw_indexed_indirect` instead of one call each.
+    // needs `INDIRECT_FIRST_INSTANCE`, since each draw's instance selects its transform.
+    multi_draw_indirect: bool,
+    shadow_maps: crate::texture::Texture,
     shadow_map_views: Vec<wgpu::TextureView>,
+    point_shadow_maps: crate::texture::Texture,
+    point_shadow_map_views: Vec<wgpu::TextureView>,
 
+    // every light of the scene, grown by `write_vertex` when it doesn't fit.
+    light_buffer: wgpu::Buffer,
+    // uploads the uniforms that change every frame, the camera's and the lights.
+    staging_belt: wgpu::util::StagingBelt,
+    light_culling: crate::light_culling::LightCulling,
+    // shadow maps, ambient occlusion and lights, everything the lighting reads besides the material.
+    shadow_map_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_sampler: wgpu::Sampler,
     shadow_map_bind_group: wgpu::BindGroup,
+    // bound as group 1 in the shadow passes.
+    empty_bind_group_layout: wgpu::BindGroupLayout,
+    empty_bind_group: wgpu::BindGroup,
+    ssao: crate::ssao::Ssao,
+
+    material_texture_bind_group_layout: wgpu::BindGroupLayout,
+    // one per distinct glTF sampler. filled by `upload_textures`.
+    material_samplers: HashMap<crate::model::Sampler, wgpu::Sampler>,
+    // shared by the material textures of every `upload_textures`.
+    mipmap_generator: crate::texture::MipmapGenerator,
+    white_texture: crate::texture::Texture,
+    flat_normal_texture: crate::texture::Texture,
+    // bound for primitives without a texture.
+    default_material_texture_bind_group: wgpu::BindGroup,
+    // keyed by glTF material index. filled by `upload_textures`.
+    material_texture_bind_groups: HashMap<usize, wgpu::BindGroup>,
+    // set by `set_material_override`.
+    material_override: Option<crate::model::Material>,
+    // index into `Scene::meshes` of the mesh drawn with an outline, set by `set_highlight`.
+    highlight: Option<usize>,
+    // (draw, instance) of every visible instance of the highlighted mesh, one per primitive.
+    highlighted_instances: Vec<(usize, u32)>,
 
     vertex_buffer: VertexBuffer,
     scene_uniform: UniformGroup,
     primitive_uniform: UniformGroup,
+    tone_map_uniform: UniformGroup,
 
     draws: Vec<Draw>,
+    // the scene version, mesh count and transmission setting `draws` were built for.
+    built_for: Option<(crate::model::SceneVersion, usize, bool)>,
+    // the transforms version of the joint matrices in the primitive uniforms.
+    transforms_for: Option<crate::model::SceneVersion>,
+    // in the vertex buffer.
+    vertex_count: u32,
     width: u32,
     height: u32,
+    // draws outside the camera frustum in the last `write_vertex`. they still cast shadows.
+    pub culled_draws: usize,
+    // returned by `stats`.
+    stats: RenderStats,
+
+    // debug: draw each light's shadow map into the bottom-left corner.
+    pub show_shadow_maps: bool,
+    // debug: draw a wireframe shape in each light's color where it is, over the scene.
+    pub show_light_gizmos: bool,
+    pub debug_view: DebugView,
+    // shadows are filtered over a (2 * pcf_radius + 1)^2 texel kernel.
+    pub pcf_radius: u32,
+    // directional light cascades cover the view from the near plane up to this distance,
+    // and point lights cast shadows up to this far from the light.
+    pub shadow_distance: f32,
+    // world space distance shadow lookups are moved towards the light.
+    pub shadow_bias: f32,
+    // world space distance shadow lookups are moved along the surface normal.
+    pub shadow_normal_bias: f32,
+    // linear color is scaled by this before tone mapping.
+    pub exposure: f32,
+    pub tone_mapping: ToneMapping,
+    // linear brightness above which pixels bloom.
+    pub bloom_threshold: f32,
+    // how much of the blurred bright pixels are added back before tone mapping.
+    pub bloom_intensity: f32,
+    // anti-aliases the tone mapped image, for when MSAA is too expensive.
+    pub fxaa_enabled: bool,
+    // KHR_materials_transmission: transmissive primitives show the scene behind them.
+    // without it they're shaded as opaque, and the extra pass is skipped.
+    pub transmission_enabled: bool,
+    // lights are skipped where their irradiance falls below this.
+    pub light_cutoff: f32,
+    // world space reach of the ambient occlusion samples.
+    pub ssao_radius: f32,
+    // 0 disables ambient occlusion.
+    pub ssao_strength: f32,
+    // material textures get a full mip chain, avoiding shimmering at a distance. read by `upload_textures`.
+    pub generate_mipmaps: bool,
+    // max anisotropic filtering samples for material textures, 1 disables it. read by `upload_textures`.
+    pub anisotropy_clamp: u16,
+    // lays down opaque depth before shading, so overlapping layers are shaded once. it pays
+    // for drawing the opaque geometry twice, so it only helps scenes with a lot of overdraw.
+    pub depth_prepass: bool,
+    // background where nothing is drawn, in linear HDR color. tone mapped like the scene.
+    pub clear_color: wgpu::Color,
+    // drawn as the background instead of `clear_color` when there's no environment.
+    pub sky_gradient: Option<SkyGradient>,
+    // world space distance between the lines of the grid shown by `show_grid`.
+    pub grid_spacing: f32,
+    // in linear HDR color, like the lit scene.
+    pub grid_color: Vec3,
 }
 
 impl Renderer {
-    pub fn new(device: &wgpu::Device, surface_configuration: &wgpu::SurfaceConfiguration) -> Self {
+    // `sample_count` must be supported by the adapter for both `HDR_FORMAT` and Depth32Float.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_configuration: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> Self {
         let depth_texture = crate::texture::Texture::create_depth_texture(
             device,
             surface_configuration.width,
             surface_configuration.height,
             1,
+            sample_count,
+        );
+        let multisampled_texture = Self::create_multisampled_texture(
+            device,
+            surface_configuration.width,
+            surface_configuration.height,
+            sample_count,
+        );
+        let hdr_texture = Self::create_hdr_texture(
+            device,
+            surface_configuration.width,
+            surface_configuration.height,
         );
 
         // Uniforms
@@ -255,14 +702,28 @@ impl Renderer {
             &[
                 size_of::<Mat4>() as u64,
                 size_of::<Vec3>() as u64,
-                4 * size_of::<crate::model::LightRaw>() as u64,
+                size_of::<u32>() as u64,
                 size_of::<Vec3>() as u64,
+                size_of::<u32>() as u64,
+                size_of::<Vec4>() as u64,
+                SHADOW_MAP_LAYERS as u64 * size_of::<Mat4>() as u64,
+                size_of::<f32>() as u64,
+                size_of::<Vec2>() as u64,
+                size_of::<u32>() as u64,
             ],
         );
-        for _ in 0..5 {
+        // bind group 0 is the main pass, bind group `layer + 1` renders that shadow map layer,
+        // followed by one per point light cube map face.
+        for _ in 0..1 + SHADOW_MAP_LAYERS + POINT_SHADOW_MAP_LAYERS {
             scene_uniform.add_bind_group(device);
         }
-        let primitive_uniform = UniformGroup::new(device, &[32]);
+        let primitive_uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<crate::model::Material>().next_multiple_of(16) as u64,
+                (MAX_JOINTS * size_of::<Mat4>()) as u64,
+            ],
+        );
 
         let shadow_map_bind_group_layout =
             device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -271,7 +732,7 @@ impl Renderer {
                     wgpu::BindGroupLayoutEntry {
                         binding: 0,
                         visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
                         count: None,
                     },
                     wgpu::BindGroupLayoutEntry {
@@ -284,111 +745,240 @@ impl Renderer {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Depth,
+                            view_dimension: wgpu::TextureViewDimension::CubeArray,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // ambient occlusion, see `Ssao`.
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // lights, see `light_buffer`.
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // lights of each tile, see `LightCulling`.
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    // the scene behind transmissive primitives, see `Transmission`.
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    // the shadow maps again as plain floats for the overlay. GL can't read a
+                    // depth texture without a comparison.
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 8,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/shader.wgsl"));
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[
-                &scene_uniform.bind_group_layout,
-                &shadow_map_bind_group_layout,
-                &primitive_uniform.bind_group_layout,
-            ],
-            immediate_size: 0,
+        // each material texture is followed by its sampler.
+        let mut material_texture_layout_entries = Vec::new();
+        for i in 0..MATERIAL_TEXTURE_COUNT {
+            material_texture_layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: i * 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            material_texture_layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: i * 2 + 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            });
+        }
+        let material_texture_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("3D: Material texture bind group layout"),
+                entries: &material_texture_layout_entries,
+            });
+
+        // shadow passes only need the primitive uniform for skinning, group 1 is left empty.
+        let empty_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Empty bind group layout"),
+            entries: &[],
+        });
+        let empty_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Empty bind group"),
+            layout: &empty_bind_group_layout,
+            entries: &[],
         });
+        let ShaderPipelines {
+            render_pipelines,
+            render_pipeline_shadow_map,
+            render_pipeline_point_shadow_map,
+            render_pipeline_full,
+            render_pipeline_prepass,
+            render_pipelines_depth_prepass,
+            render_pipeline_outline_mask,
+            render_pipeline_outline,
+        } = Self::create_shader_pipelines(
+            device,
+            &device.create_shader_module(wgpu::include_wgsl!("shader/shader.wgsl")),
+            &scene_uniform.bind_group_layout,
+            &shadow_map_bind_group_layout,
+            &primitive_uniform.bind_group_layout,
+            &material_texture_bind_group_layout,
+            &empty_bind_group_layout,
+            sample_count,
+            surface_configuration.format,
+        );
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("3D"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                compilation_options: Default::default(),
-                buffers: &[Vertex::desc(), Instance::desc()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: Default::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_configuration.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview_mask: None,
-            cache: None,
+        let mut tone_map_uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<f32>() as u64,
+                size_of::<u32>() as u64,
+                size_of::<f32>() as u64,
+            ],
+        );
+        tone_map_uniform.add_bind_group(device);
+        let fxaa = crate::fxaa::Fxaa::new(
+            device,
+            surface_configuration.format,
+            surface_configuration.width,
+            surface_configuration.height,
+        );
+        let bloom = crate::bloom::Bloom::new(
+            device,
+            &hdr_texture.view,
+            surface_configuration.width,
+            surface_configuration.height,
+        );
+        let hdr_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("HDR bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
         });
+        // upscales the half resolution bloom.
+        let bloom_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let hdr_bind_group = Self::create_hdr_bind_group(
+            device,
+            &hdr_bind_group_layout,
+            &hdr_texture,
+            &bloom,
+            &bloom_sampler,
+        );
 
+        let tone_map_shader =
+            device.create_shader_module(wgpu::include_wgsl!("shader/tone_map.wgsl"));
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&scene_uniform.bind_group_layout],
+            bind_group_layouts: &[&tone_map_uniform.bind_group_layout, &hdr_bind_group_layout],
             immediate_size: 0,
         });
-        let render_pipeline_shadow_map =
+        let render_pipeline_tone_map =
             device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Shadow map"),
+                label: Some("Tone map"),
                 layout: Some(&pipeline_layout),
                 vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_light"),
+                    module: &tone_map_shader,
+                    entry_point: Some("vs_full"),
                     compilation_options: Default::default(),
-                    buffers: &[Vertex::desc(), Instance::desc()],
+                    buffers: &[],
                 },
                 fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_light"),
+                    module: &tone_map_shader,
+                    entry_point: Some("fs_tone_map"),
                     compilation_options: Default::default(),
-                    targets: &[],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: surface_configuration.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
                 }),
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
                 multiview_mask: None,
                 cache: None,
             });
 
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: None,
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
@@ -396,14 +986,62 @@ impl Renderer {
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
             mipmap_filter: wgpu::MipmapFilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
             ..Default::default()
         });
 
+        let anisotropy_clamp = crate::texture::MAX_ANISOTROPY;
+        let default_sampler = crate::texture::Texture::create_material_sampler(
+            device,
+            &crate::model::Sampler::default(),
+            anisotropy_clamp,
+        );
+        let white_texture = crate::texture::Texture::from_rgba8(
+            device,
+            queue,
+            1,
+            1,
+            &[255, 255, 255, 255],
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            None,
+            Some("white texture"),
+        );
+        // tangent space +Z, i.e. the unperturbed normal.
+        let flat_normal_texture = crate::texture::Texture::from_rgba8(
+            device,
+            queue,
+            1,
+            1,
+            &[128, 128, 255, 255],
+            wgpu::TextureFormat::Rgba8Unorm,
+            None,
+            Some("flat normal texture"),
+        );
+        let default_material_texture_bind_group = Self::create_material_texture_bind_group(
+            device,
+            &material_texture_bind_group_layout,
+            [
+                &white_texture,
+                &flat_normal_texture,
+                &white_texture,
+                &white_texture,
+                &white_texture,
+                &flat_normal_texture,
+                &white_texture,
+            ]
+            .map(|texture| (texture, &default_sampler)),
+        );
+
         let vertex_buffer = VertexBuffer::new(device);
 
-        let shadow_maps: crate::texture::Texture =
-            crate::texture::Texture::create_depth_texture(device, 1024, 1024, 4);
-        let shadow_map_views: Vec<wgpu::TextureView> = (0..4)
+        let shadow_maps: crate::texture::Texture = crate::texture::Texture::create_depth_texture(
+            device,
+            SHADOW_MAP_SIZE,
+            SHADOW_MAP_SIZE,
+            SHADOW_MAP_LAYERS,
+            1,
+        );
+        let shadow_map_views: Vec<wgpu::TextureView> = (0..SHADOW_MAP_LAYERS)
             .map(|i| {
                 shadow_maps
                     .texture
@@ -415,36 +1053,557 @@ impl Renderer {
             })
             .collect();
 
-        let shadow_map_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &shadow_map_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&shadow_maps.view),
-                },
-            ],
-        });
+        let point_shadow_maps = crate::texture::Texture::create_depth_cube_texture(
+            device,
+            POINT_SHADOW_MAP_SIZE,
+            POINT_SHADOW_MAP_LAYERS / 6,
+        );
+        let point_shadow_map_views: Vec<wgpu::TextureView> = (0..POINT_SHADOW_MAP_LAYERS)
+            .map(|i| {
+                point_shadow_maps
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor {
+                        dimension: Some(wgpu::TextureViewDimension::D2),
+                        base_array_layer: i,
+                        array_layer_count: Some(1),
+                        ..Default::default()
+                    })
+            })
+            .collect();
+
+        let ssao = crate::ssao::Ssao::new(
+            device,
+            surface_configuration.width,
+            surface_configuration.height,
+        );
+        let light_buffer = Self::create_light_buffer(device, MAX_SHADOWED_LIGHTS as usize);
+        let light_culling = crate::light_culling::LightCulling::new(
+            device,
+            surface_configuration.width,
+            surface_configuration.height,
+            ssao.gbuffer_views()[0],
+            &light_buffer,
+        );
+        let transmission = crate::transmission::Transmission::new(
+            device,
+            &hdr_texture.view,
+            surface_configuration.width,
+            surface_configuration.height,
+        );
+        let shadow_map_bind_group = Self::create_shadow_map_bind_group(
+            device,
+            &shadow_map_bind_group_layout,
+            &shadow_sampler,
+            &shadow_maps,
+            &point_shadow_maps,
+            &ssao,
+            &light_buffer,
+            &light_culling,
+            &transmission,
+        );
 
         Self {
-            render_pipeline,
+            render_pipelines,
             render_pipeline_shadow_map,
+            render_pipeline_point_shadow_map,
+            render_pipeline_full,
+            render_pipeline_tone_map,
+            render_pipeline_prepass,
+            render_pipelines_depth_prepass,
+            render_pipeline_outline_mask,
+            render_pipeline_outline,
 
             depth_texture,
+            outline_stencil: Self::create_outline_stencil(
+                device,
+                surface_configuration.width,
+                surface_configuration.height,
+            ),
+            hdr_texture,
+            hdr_bind_group_layout,
+            hdr_bind_group,
+            bloom,
+            transmission,
+            skybox: crate::skybox::Skybox::new(device, sample_count),
+            grid: crate::grid::Grid::new(device, sample_count),
+            grid_visible: false,
+            light_gizmos: crate::light_gizmos::LightGizmos::new(
+                device,
+                &scene_uniform.bind_group_layout,
+                sample_count,
+            ),
+            fxaa,
+            bloom_sampler,
+            multisampled_texture,
+            sample_count,
+            output_format: surface_configuration.format,
+            multi_draw_indirect: device
+                .features()
+                .contains(wgpu::Features::INDIRECT_FIRST_INSTANCE),
+            shadow_maps,
             shadow_map_views,
+            point_shadow_maps,
+            point_shadow_map_views,
+            light_buffer,
+            staging_belt: wgpu::util::StagingBelt::new(device.clone(), STAGING_BELT_CHUNK_SIZE),
+            light_culling,
+            shadow_map_bind_group_layout,
+            shadow_sampler,
             shadow_map_bind_group,
+            empty_bind_group_layout,
+            empty_bind_group,
+            ssao,
+
+            material_texture_bind_group_layout,
+            material_samplers: HashMap::from([(crate::model::Sampler::default(), default_sampler)]),
+            mipmap_generator: crate::texture::MipmapGenerator::new(device),
+            white_texture,
+            flat_normal_texture,
+            default_material_texture_bind_group,
+            material_texture_bind_groups: HashMap::new(),
+            material_override: None,
+            highlight: None,
+            highlighted_instances: Vec::new(),
 
             vertex_buffer,
             scene_uniform,
             primitive_uniform,
+            tone_map_uniform,
 
             draws: Vec::new(),
+            built_for: None,
+            transforms_for: None,
+            vertex_count: 0,
             width: surface_configuration.width,
             height: surface_configuration.height,
+            culled_draws: 0,
+            stats: RenderStats::default(),
+
+            show_shadow_maps: false,
+            show_light_gizmos: false,
+            debug_view: DebugView::Off,
+            pcf_radius: 1,
+            shadow_distance: 50.0,
+            shadow_bias: 0.01,
+            shadow_normal_bias: 0.02,
+            exposure: 1.0,
+            tone_mapping: ToneMapping::Aces,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.05,
+            fxaa_enabled: false,
+            transmission_enabled: true,
+            light_cutoff: 0.001,
+            ssao_radius: 0.5,
+            ssao_strength: 1.0,
+            generate_mipmaps: true,
+            anisotropy_clamp,
+            depth_prepass: false,
+            clear_color: wgpu::Color::BLACK,
+            sky_gradient: None,
+            grid_spacing: 1.0,
+            grid_color: Vec3::splat(0.5),
+        }
+    }
+
+    // rebuilds the pipelines of shader.wgsl from `source`, e.g. an edited copy of the file.
+    // on a WGSL or pipeline validation error, the current pipelines are kept.
+    pub async fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        source: &str,
+    ) -> Result<(), wgpu::Error> {
+        let error_scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("shader.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipelines = Self::create_shader_pipelines(
+            device,
+            &shader,
+            &self.scene_uniform.bind_group_layout,
+            &self.shadow_map_bind_group_layout,
+            &self.primitive_uniform.bind_group_layout,
+            &self.material_texture_bind_group_layout,
+            &self.empty_bind_group_layout,
+            self.sample_count,
+            self.output_format,
+        );
+        if let Some(err) = error_scope.pop().await {
+            return Err(err);
+        }
+
+        ShaderPipelines {
+            render_pipelines: self.render_pipelines,
+            render_pipeline_shadow_map: self.render_pipeline_shadow_map,
+            render_pipeline_point_shadow_map: self.render_pipeline_point_shadow_map,
+            render_pipeline_full: self.render_pipeline_full,
+            render_pipeline_prepass: self.render_pipeline_prepass,
+            render_pipelines_depth_prepass: self.render_pipelines_depth_prepass,
+            render_pipeline_outline_mask: self.render_pipeline_outline_mask,
+            render_pipeline_outline: self.render_pipeline_outline,
+        } = pipelines;
+        Ok(())
+    }
+
+    // every pipeline built from shader.wgsl, with `shader` compiled from it.
+    #[allow(clippy::too_many_arguments)]
+    fn create_shader_pipelines(
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        scene_bind_group_layout: &wgpu::BindGroupLayout,
+        shadow_map_bind_group_layout: &wgpu::BindGroupLayout,
+        primitive_bind_group_layout: &wgpu::BindGroupLayout,
+        material_texture_bind_group_layout: &wgpu::BindGroupLayout,
+        empty_bind_group_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+        output_format: wgpu::TextureFormat,
+    ) -> ShaderPipelines {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                scene_bind_group_layout,
+                shadow_map_bind_group_layout,
+                primitive_bind_group_layout,
+                material_texture_bind_group_layout,
+            ],
+            immediate_size: 0,
+        });
+
+        let primitive_state = |topology, double_sided| wgpu::PrimitiveState {
+            topology: match topology {
+                crate::model::Topology::Triangles => wgpu::PrimitiveTopology::TriangleList,
+                crate::model::Topology::Lines => wgpu::PrimitiveTopology::LineList,
+                crate::model::Topology::Points => wgpu::PrimitiveTopology::PointList,
+            },
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: if double_sided {
+                None
+            } else {
+                Some(wgpu::Face::Back)
+            },
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        };
+        let create_render_pipeline = |key: PipelineKey| {
+            // transparent surfaces are depth tested against opaque geometry but don't occlude each other.
+            let (blend, depth_write_enabled, depth_compare) = if key.transparent {
+                (
+                    wgpu::BlendState::ALPHA_BLENDING,
+                    false,
+                    wgpu::CompareFunction::Greater,
+                )
+            } else if key.depth_prepass {
+                (
+                    wgpu::BlendState::REPLACE,
+                    false,
+                    wgpu::CompareFunction::Equal,
+                )
+            } else {
+                (
+                    wgpu::BlendState::REPLACE,
+                    true,
+                    wgpu::CompareFunction::Greater,
+                )
+            };
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("3D"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_main"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend: Some(blend),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: primitive_state(key.topology, key.double_sided),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Depth32Float,
+                    depth_write_enabled,
+                    depth_compare,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        let mut render_pipelines = HashMap::new();
+        for topology in [
+            crate::model::Topology::Triangles,
+            crate::model::Topology::Lines,
+            crate::model::Topology::Points,
+        ] {
+            for transparent in [false, true] {
+                for double_sided in [false, true] {
+                    // transparent primitives, points and lines aren't in the depth prepass.
+                    let in_depth_prepass =
+                        !transparent && topology == crate::model::Topology::Triangles;
+                    for depth_prepass in [false, in_depth_prepass] {
+                        let key = PipelineKey {
+                            topology,
+                            transparent,
+                            double_sided,
+                            depth_prepass,
+                        };
+                        render_pipelines
+                            .entry(key)
+                            .or_insert_with(|| create_render_pipeline(key));
+                    }
+                }
+            }
+        }
+
+        // fills the depth buffer before the lit pass, so that it shades each pixel once.
+        let render_pipelines_depth_prepass = [false, true].map(|double_sided| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Depth prepass"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_depth"),
+                    compilation_options: Default::default(),
+                    // the lit pass' color target, left untouched.
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::empty(),
+                    })],
+                }),
+                primitive: primitive_state(crate::model::Topology::Triangles, double_sided),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Depth32Float,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Greater,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview_mask: None,
+                cache: None,
+            })
+        });
+
+        // writes the G-buffer for `Ssao`. transparent primitives are skipped, so it never blends.
+        let render_pipeline_prepass =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Prepass"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_prepass"),
+                    compilation_options: Default::default(),
+                    targets: &[
+                        Some(crate::ssao::POSITION_FORMAT.into()),
+                        Some(crate::ssao::NORMAL_FORMAT.into()),
+                    ],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Depth32Float,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Greater,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            });
+
+        // shadow passes only need the primitive uniform for skinning, group 1 is left empty.
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[
+                scene_bind_group_layout,
+                empty_bind_group_layout,
+                primitive_bind_group_layout,
+            ],
+            immediate_size: 0,
+        });
+        let create_shadow_pipeline = |label, vs_entry_point, fs_entry_point| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some(vs_entry_point),
+                    compilation_options: Default::default(),
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some(fs_entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Depth32Float,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: SHADOW_DEPTH_BIAS,
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        let render_pipeline_shadow_map =
+            create_shadow_pipeline("Shadow map", "vs_light", "fs_light");
+        let render_pipeline_point_shadow_map =
+            create_shadow_pipeline("Point shadow map", "vs_point_light", "fs_point_light");
+
+        // drawn over the output after tone mapping, so the outline keeps its color at any exposure.
+        // the mask pass writes 1 wherever the highlighted mesh is, the outline pass draws the mesh
+        // grown along its normals everywhere else.
+        let create_outline_pipeline = |label, vs_entry_point, write_mask, stencil_face| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some(vs_entry_point),
+                    compilation_options: Default::default(),
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_outline"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: output_format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask,
+                    })],
+                }),
+                // both sides, the outline is around the silhouette whichever way the mesh faces.
+                primitive: primitive_state(crate::model::Topology::Triangles, true),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Stencil8,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState {
+                        front: stencil_face,
+                        back: stencil_face,
+                        read_mask: !0,
+                        write_mask: !0,
+                    },
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        let render_pipeline_outline_mask = create_outline_pipeline(
+            "Outline mask",
+            "vs_light",
+            wgpu::ColorWrites::empty(),
+            wgpu::StencilFaceState {
+                compare: wgpu::CompareFunction::Always,
+                fail_op: wgpu::StencilOperation::Keep,
+                depth_fail_op: wgpu::StencilOperation::Keep,
+                pass_op: wgpu::StencilOperation::Replace,
+            },
+        );
+        let render_pipeline_outline = create_outline_pipeline(
+            "Outline",
+            "vs_outline",
+            wgpu::ColorWrites::ALL,
+            wgpu::StencilFaceState {
+                compare: wgpu::CompareFunction::NotEqual,
+                fail_op: wgpu::StencilOperation::Keep,
+                depth_fail_op: wgpu::StencilOperation::Keep,
+                pass_op: wgpu::StencilOperation::Keep,
+            },
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[scene_bind_group_layout, shadow_map_bind_group_layout],
+            immediate_size: 0,
+        });
+        let render_pipeline_full = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Full screen"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_full"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_full"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: output_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            // drawn over the resolved output, so it is never multisampled.
+            multisample: wgpu::MultisampleState::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        ShaderPipelines {
+            render_pipelines,
+            render_pipeline_shadow_map,
+            render_pipeline_point_shadow_map,
+            render_pipeline_full,
+            render_pipeline_prepass,
+            render_pipelines_depth_prepass,
+            render_pipeline_outline_mask,
+            render_pipeline_outline,
         }
     }
 
@@ -452,11 +1611,430 @@ impl Renderer {
         self.width = width;
         self.height = height;
 
-        self.depth_texture =
-            crate::texture::Texture::create_depth_texture(device, width, height, 1);
+        self.depth_texture = crate::texture::Texture::create_depth_texture(
+            device,
+            width,
+            height,
+            1,
+            self.sample_count,
+        );
+        self.multisampled_texture =
+            Self::create_multisampled_texture(device, width, height, self.sample_count);
+        self.outline_stencil = Self::create_outline_stencil(device, width, height);
+        self.hdr_texture = Self::create_hdr_texture(device, width, height);
+        self.bloom
+            .resize(device, &self.hdr_texture.view, width, height);
+        self.hdr_bind_group = Self::create_hdr_bind_group(
+            device,
+            &self.hdr_bind_group_layout,
+            &self.hdr_texture,
+            &self.bloom,
+            &self.bloom_sampler,
+        );
+        self.transmission
+            .resize(device, &self.hdr_texture.view, width, height);
+        self.fxaa.resize(device, width, height);
+        self.ssao.resize(device, width, height);
+        self.create_lighting_bind_groups(device);
+    }
+
+    // after the screen size or `light_buffer` changes.
+    fn create_lighting_bind_groups(&mut self, device: &wgpu::Device) {
+        self.light_culling.resize(
+            device,
+            self.width,
+            self.height,
+            self.ssao.gbuffer_views()[0],
+            &self.light_buffer,
+        );
+        self.shadow_map_bind_group = Self::create_shadow_map_bind_group(
+            device,
+            &self.shadow_map_bind_group_layout,
+            &self.shadow_sampler,
+            &self.shadow_maps,
+            &self.point_shadow_maps,
+            &self.ssao,
+            &self.light_buffer,
+            &self.light_culling,
+            &self.transmission,
+        );
+    }
+
+    // room for `capacity` lights.
+    fn create_light_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("lights"),
+            size: (capacity * size_of::<crate::model::LightRaw>()) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_shadow_map_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        shadow_maps: &crate::texture::Texture,
+        point_shadow_maps: &crate::texture::Texture,
+        ssao: &crate::ssao::Ssao,
+        light_buffer: &wgpu::Buffer,
+        light_culling: &crate::light_culling::LightCulling,
+        transmission: &crate::transmission::Transmission,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&shadow_maps.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&point_shadow_maps.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(ssao.occlusion_view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: light_culling.tile_buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(transmission.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::Sampler(transmission.sampler()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::TextureView(&shadow_maps.view),
+                },
+            ],
+        })
+    }
+
+    fn create_outline_stencil(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> crate::texture::Texture {
+        crate::texture::Texture::create_render_target(
+            device,
+            width,
+            height,
+            wgpu::TextureFormat::Stencil8,
+            1,
+            Some("outline stencil"),
+        )
+    }
+
+    fn create_multisampled_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> Option<crate::texture::Texture> {
+        (sample_count > 1).then(|| {
+            crate::texture::Texture::create_render_target(
+                device,
+                width,
+                height,
+                HDR_FORMAT,
+                sample_count,
+                Some("multisampled color"),
+            )
+        })
+    }
+
+    fn create_hdr_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> crate::texture::Texture {
+        crate::texture::Texture::create_render_target(
+            device,
+            width,
+            height,
+            HDR_FORMAT,
+            1,
+            Some("hdr color"),
+        )
+    }
+
+    fn create_hdr_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        hdr_texture: &crate::texture::Texture,
+        bloom: &crate::bloom::Bloom,
+        bloom_sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("hdr bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&hdr_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(bloom.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(bloom_sampler),
+                },
+            ],
+        })
+    }
+
+    // `textures` are in binding order: base color, normal, metallic-roughness, occlusion,
+    // emissive, clearcoat normal and transmission, each with its sampler.
+    fn create_material_texture_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        textures: [(&crate::texture::Texture, &wgpu::Sampler); MATERIAL_TEXTURE_COUNT as usize],
+    ) -> wgpu::BindGroup {
+        let mut entries = Vec::new();
+        for (i, (texture, sampler)) in textures.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: i as u32 * 2,
+                resource: wgpu::BindingResource::TextureView(&texture.view),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: i as u32 * 2 + 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            });
+        }
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &entries,
+        })
+    }
+
+    // upload the textures of every material. call once after loading a scene.
+    // each image is uploaded once per format it's used with, however many materials share it.
+    pub fn upload_textures(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &crate::model::Scene,
+    ) {
+        // in binding order. colors are sRGB encoded, the others hold linear data.
+        const FORMATS: [wgpu::TextureFormat; MATERIAL_TEXTURE_COUNT as usize] = [
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba8Unorm,
+        ];
+        let mut textures: HashMap<(usize, wgpu::TextureFormat), crate::texture::Texture> =
+            HashMap::new();
+
+        let default_sampler = crate::model::Sampler::default();
+        self.material_samplers = HashMap::from([(
+            default_sampler,
+            crate::texture::Texture::create_material_sampler(
+                device,
+                &default_sampler,
+                self.anisotropy_clamp,
+            ),
+        )]);
+        self.material_texture_bind_groups = HashMap::new();
+        for mesh in scene.meshes.iter() {
+            for primitive in mesh.primitives.iter() {
+                // the default material has no textures.
+                let Some(material_index) = primitive.material_index else {
+                    continue;
+                };
+                if self
+                    .material_texture_bind_groups
+                    .contains_key(&material_index)
+                {
+                    continue;
+                }
+                let images = [
+                    primitive.base_color_texture,
+                    primitive.normal_texture,
+                    primitive.metallic_roughness_texture,
+                    primitive.occlusion_texture,
+                    primitive.emissive_texture,
+                    primitive.clearcoat_normal_texture,
+                    primitive.transmission_texture,
+                ];
+                if images.iter().all(Option::is_none) {
+                    continue;
+                }
+
+                for (&texture, format) in images.iter().zip(FORMATS) {
+                    if let Some(crate::model::TextureRef { image, sampler }) = texture {
+                        self.material_samplers.entry(sampler).or_insert_with(|| {
+                            crate::texture::Texture::create_material_sampler(
+                                device,
+                                &sampler,
+                                self.anisotropy_clamp,
+                            )
+                        });
+                        textures.entry((image, format)).or_insert_with(|| {
+                            let image = &scene.images[image];
+                            crate::texture::Texture::from_rgba8(
+                                device,
+                                queue,
+                                image.width,
+                                image.height,
+                                &image.pixels,
+                                format,
+                                self.generate_mipmaps.then_some(&mut self.mipmap_generator),
+                                Some("material texture"),
+                            )
+                        });
+                    }
+                }
+                let fallbacks = [
+                    &self.white_texture,
+                    &self.flat_normal_texture,
+                    &self.white_texture,
+                    &self.white_texture,
+                    &self.white_texture,
+                    &self.flat_normal_texture,
+                    &self.white_texture,
+                ];
+                let bind_group = Self::create_material_texture_bind_group(
+                    device,
+                    &self.material_texture_bind_group_layout,
+                    std::array::from_fn(|i| match images[i] {
+                        Some(texture) => (
+                            &textures[&(texture.image, FORMATS[i])],
+                            &self.material_samplers[&texture.sampler],
+                        ),
+                        None => (fallbacks[i], &self.material_samplers[&default_sampler]),
+                    }),
+                );
+                self.material_texture_bind_groups
+                    .insert(material_index, bind_group);
+            }
+        }
+        self.mipmap_generator.submit(device, queue);
+    }
+
+    // `exposure` in stops, 0 leaves the linear color as it is and each stop doubles it.
+    pub fn exposure_ev(&self) -> f32 {
+        self.exposure.log2()
+    }
+
+    pub fn set_exposure_ev(&mut self, ev: f32) {
+        self.exposure = ev.exp2();
+    }
+
+    // shades every primitive with `material` instead of its own, without textures, to tell
+    // lighting issues apart from material data. `None` goes back to the scene's materials.
+    // applied by the next `write_vertex`.
+    pub fn set_material_override(&mut self, material: Option<crate::model::Material>) {
+        self.material_override = material;
+        self.built_for = None;
+    }
+
+    pub fn material_override(&self) -> Option<&crate::model::Material> {
+        self.material_override.as_ref()
+    }
+
+    // outlines the mesh at this index in `Scene::meshes`, to show it's selected. only its
+    // triangles are outlined, skinned meshes in their bind pose. applied by the next `write_vertex`.
+    pub fn set_highlight(&mut self, mesh: Option<usize>) {
+        self.highlight = mesh;
+    }
+
+    pub fn highlight(&self) -> Option<usize> {
+        self.highlight
+    }
+
+    // draws an infinite reference grid on the XZ plane, `grid_spacing` apart.
+    pub fn show_grid(&mut self, show: bool) {
+        self.grid_visible = show;
+    }
+
+    pub fn is_grid_shown(&self) -> bool {
+        self.grid_visible
+    }
+
+    // the cube map drawn behind the scene. `None` falls back to the clear color.
+    pub fn set_environment(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: Option<&[crate::model::Image; 6]>,
+    ) {
+        self.skybox.set_environment(device, queue, faces);
+    }
+
+    pub fn write_vertex(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &crate::model::Scene,
+    ) {
+        let lights = scene.all_lights();
+        let light_capacity =
+            self.light_buffer.size() as usize / size_of::<crate::model::LightRaw>();
+        if lights.len() > light_capacity {
+            self.light_buffer = Self::create_light_buffer(device, lights.len().next_power_of_two());
+            self.create_lighting_bind_groups(device);
+        }
+        if self.show_light_gizmos {
+            self.light_gizmos.write(device, queue, &lights);
+        }
+
+        // the draws and their geometry only depend on the scene and the transmission setting.
+        // the camera just culls their instances, which are written every frame.
+        let key = (
+            scene.version(),
+            scene.meshes.len(),
+            self.transmission_enabled,
+        );
+        // the geometry is only uploaded when the scene changed.
+        let uploaded = self.built_for != Some(key);
+        if uploaded {
+            self.built_for = Some(key);
+            self.build_draws(device, queue, scene);
+        }
+        // skinned vertices are placed by the joint matrices in their primitive uniforms.
+        if self.transforms_for != Some(scene.transforms_version()) {
+            self.transforms_for = Some(scene.transforms_version());
+            for draw in self.draws.iter().filter(|draw| draw.skinned) {
+                if let Some(skin) = scene.meshes[draw.meshes[0]].skin {
+                    self.primitive_uniform.write_binding(
+                        queue,
+                        draw.uniform_id,
+                        1,
+                        bytemuck::cast_slice(&scene.skins[skin].joint_matrices),
+                    );
+                }
+            }
+        }
+        self.stats.instances = self.write_instances(device, queue, scene);
+        self.stats.vertices = if uploaded { self.vertex_count } else { 0 };
+        self.stats.culled_draws = self.culled_draws as u32;
     }
 
-    pub fn write_vertex(
+    // lays out the scene's primitives as draws and uploads their geometry and primitive uniforms.
+    fn build_draws(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -464,55 +2042,240 @@ impl Renderer {
     ) {
         let mut vertices: Vec<Vertex> = Vec::new();
         let mut indices: Vec<u32> = Vec::new();
-        let mut instances: Vec<Instance> = Vec::new();
+        let mut instance_count = 0;
         self.draws = Vec::new();
-        for mesh in scene.meshes.iter() {
-            for primitive in mesh.primitives.iter() {
-                let instance_num = instances.len() as u32;
-                while !self.primitive_uniform.has_bind_group(instance_num as u64) {
+
+        let material = |primitive: &crate::model::Primitive| {
+            self.material_override.unwrap_or(primitive.material)
+        };
+        // transmissive primitives are drawn after the rest of the scene, like transparent ones.
+        let transmissive = |primitive: &crate::model::Primitive| {
+            self.transmission_enabled
+                && material(primitive).flags & crate::model::Material::TRANSMISSION != 0
+        };
+        // drawn back to front once the opaque primitives are done.
+        let sorted = |primitive: &crate::model::Primitive| {
+            primitive.alpha_mode == crate::model::AlphaMode::Blend || transmissive(primitive)
+        };
+
+        // copies of a glTF primitive placed by different nodes are drawn as instances of it,
+        // so their geometry is uploaded once. skinned primitives are placed by their joints and
+        // sorted ones are sorted one by one, so those keep one instance each.
+        // the meshes are kept as indices into `scene.meshes`.
+        let mut groups: Vec<(&crate::model::Primitive, Vec<usize>)> = Vec::new();
+        let mut group_of: HashMap<(usize, usize), usize> = HashMap::new();
+        for (mesh_index, mesh) in scene.meshes.iter().enumerate() {
+            for (i, primitive) in mesh.primitives.iter().enumerate() {
+                if mesh.skin.is_none() && !sorted(primitive) {
+                    if let Some(&group) = group_of.get(&(mesh.mesh_index, i)) {
+                        groups[group].1.push(mesh_index);
+                        continue;
+                    }
+                    group_of.insert((mesh.mesh_index, i), groups.len());
+                }
+                groups.push((primitive, vec![mesh_index]));
+            }
+        }
+
+        // primitives sharing a topology, material and skin are laid out next to each other, so a batch
+        // is a contiguous range of draws with one pipeline, primitive uniform and texture bind group.
+        // triangles come first, the shadow passes only draw those.
+        let batch_key = |(primitive, meshes): &(&crate::model::Primitive, Vec<usize>)| {
+            (
+                primitive.topology,
+                primitive.material_index,
+                scene.meshes[meshes[0]].skin,
+            )
+        };
+        groups.sort_by_key(batch_key);
+
+        let mut uniform_id = 0;
+        for (i, (primitive, meshes)) in groups.iter().enumerate() {
+            let skin = scene.meshes[meshes[0]].skin;
+            // primitives built in code have no material index, but can still differ in material.
+            let batch_start = i == 0
+                || batch_key(&groups[i - 1]) != batch_key(&groups[i])
+                || bytemuck::bytes_of(&groups[i - 1].0.material)
+                    != bytemuck::bytes_of(&primitive.material);
+            if batch_start {
+                if i > 0 {
+                    uniform_id += 1;
+                }
+                while !self.primitive_uniform.has_bind_group(uniform_id) {
                     self.primitive_uniform.add_bind_group(device);
                 }
+                let joint_matrices: &[Mat4] = match skin {
+                    Some(skin) => &scene.skins[skin].joint_matrices,
+                    None => &[],
+                };
+                // without the transmission pass there's nothing behind to sample.
+                let mut material = material(primitive);
+                if !self.transmission_enabled {
+                    material.flags &= !crate::model::Material::TRANSMISSION;
+                }
                 self.primitive_uniform.write(
                     queue,
-                    instance_num as u64,
-                    &[bytemuck::cast_slice(&[primitive.material])],
+                    uniform_id,
+                    &[
+                        bytemuck::cast_slice(&[material]),
+                        bytemuck::cast_slice(joint_matrices),
+                    ],
                 );
-
-                let base_index = vertices.len() as i32;
-                self.draws.push(Draw {
-                    index_start: indices.len() as u32,
-                    index_end: indices.len() as u32 + primitive.indices.len() as u32,
-                    base_index,
-                    instance_num,
-                });
-                vertices.extend_from_slice(primitive.vertices.as_slice());
-                indices.extend_from_slice(primitive.indices.as_slice());
-
-                instances.push(Instance {
-                    model: mesh.transform.matrix(),
-                    rot: mesh.transform.rot(),
-                });
             }
+
+            let base_index = vertices.len() as i32;
+            self.draws.push(Draw {
+                index_start: indices.len() as u32,
+                index_end: indices.len() as u32 + primitive.indices.len() as u32,
+                base_index,
+                instance_num: instance_count,
+                instance_count: meshes.len() as u32,
+                visible_instances: 0,
+                meshes: meshes.clone(),
+                aabb_min: primitive.aabb_min,
+                aabb_max: primitive.aabb_max,
+                uniform_id,
+                material_index: primitive.material_index,
+                alpha_mode: primitive.alpha_mode,
+                double_sided: primitive.double_sided,
+                topology: primitive.topology,
+                center: primitive.center(),
+                skinned: skin.is_some(),
+                transmissive: transmissive(primitive),
+            });
+            vertices.extend_from_slice(primitive.vertices.as_slice());
+            indices.extend_from_slice(primitive.indices.as_slice());
+            instance_count += meshes.len() as u32;
         }
+
+        self.vertex_count = vertices.len() as u32;
         self.vertex_buffer
-            .write(device, queue, &vertices, &indices, &instances);
+            .write_geometry(device, queue, &vertices, &indices);
     }
 
-    pub fn render_shadow_map(
-        &self,
+    // culls the instances of every draw against the camera frustum and uploads them with their
+    // draw arguments. visible instances go first, so the camera's passes draw a prefix of the range.
+    // each instance keeps its slot while it stays on the same side of the frustum, so only moved
+    // ones are uploaded again. returns how many were.
+    fn write_instances(
+        &mut self,
+        device: &wgpu::Device,
         queue: &wgpu::Queue,
-        shadow_map_view: &wgpu::TextureView,
+        scene: &crate::model::Scene,
+    ) -> u32 {
+        let frustum_planes = scene
+            .camera
+            .frustum_planes(self.width as f32 / self.height as f32);
+        let mut instances: Vec<Instance> = Vec::new();
+        self.highlighted_instances.clear();
+        for (draw_index, draw) in self.draws.iter_mut().enumerate() {
+            // the bounding box is in bind pose, it doesn't bound skinned vertices.
+            let (visible, culled): (Vec<usize>, Vec<_>) = draw.meshes.iter().partition(|&&mesh| {
+                let mesh = &scene.meshes[mesh];
+                mesh.skin.is_some()
+                    || Self::aabb_in_frustum(
+                        &frustum_planes,
+                        mesh.transform.matrix(),
+                        draw.aabb_min,
+                        draw.aabb_max,
+                    )
+            });
+            draw.visible_instances = visible.len() as u32;
+            if let Some(highlight) = self.highlight
+                && draw.topology == crate::model::Topology::Triangles
+                && let Some(slot) = visible.iter().position(|&mesh| mesh == highlight)
+            {
+                self.highlighted_instances
+                    .push((draw_index, draw.instance_num + slot as u32));
+            }
+            instances.extend(visible.iter().chain(culled.iter()).map(|&mesh| {
+                let transform = &scene.meshes[mesh].transform;
+                Instance {
+                    model: transform.matrix(),
+                    rot: transform.rot(),
+                }
+            }));
+        }
+
+        // the shadow passes draw every instance, the camera's passes only the visible ones.
+        let indirect_args: Vec<wgpu::util::DrawIndexedIndirectArgs> = [false, true]
+            .into_iter()
+            .flat_map(|camera| {
+                self.draws
+                    .iter()
+                    .map(move |draw| wgpu::util::DrawIndexedIndirectArgs {
+                        index_count: draw.index_end - draw.index_start,
+                        instance_count: draw.instances(camera),
+                        first_index: draw.index_start,
+                        base_vertex: draw.base_index,
+                        first_instance: draw.instance_num,
+                    })
+            })
+            .collect();
+        let uploaded =
+            self.vertex_buffer
+                .write_instances(device, queue, &instances, &indirect_args);
+        self.culled_draws = self
+            .draws
+            .iter()
+            .map(|draw| (draw.instance_count - draw.visible_instances) as usize)
+            .sum();
+        uploaded
+    }
+
+    // counts of what the last frame drew, to see what a scene costs.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    // whether the local space box transformed by `model` may intersect the frustum.
+    // conservative: boxes near frustum corners can pass without being visible.
+    fn aabb_in_frustum(planes: &[Vec4; 6], model: Mat4, min: Vec3, max: Vec3) -> bool {
+        // world space box around the transformed one.
+        let center = model.transform_point3((min + max) * 0.5);
+        let half_extent = Mat3::from_mat4(model).abs() * ((max - min) * 0.5);
+        planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            normal.dot(center) + normal.abs().dot(half_extent) + plane.w >= 0.0
+        })
+    }
+
+    // `light_pos` is only read by the point light pipeline, which stores distance from it instead of depth.
+    fn write_shadow_map_uniform(
+        &mut self,
         command_encoder: &mut wgpu::CommandEncoder,
-        light: &crate::model::Light,
+        camera_matrix: Mat4,
+        light_pos: Vec3,
         idx: u64,
     ) {
-        let camera_matrix = light.matrix();
-        self.scene_uniform.write(
-            queue,
+        self.scene_uniform.write_staged(
+            &mut self.staging_belt,
+            command_encoder,
             idx,
-            &[bytemuck::cast_slice(&[camera_matrix]), &[], &[]],
+            &[
+                bytemuck::cast_slice(&[camera_matrix]),
+                bytemuck::cast_slice(&[light_pos]),
+                &[],
+                &[],
+                &[],
+                &[],
+                &[],
+                bytemuck::cast_slice(&[self.shadow_distance]),
+            ],
         );
+    }
 
+    // draws the shadow casters into one shadow map, with the scene uniform `idx` written by
+    // `write_shadow_map_uniform`.
+    fn render_shadow_map(
+        &self,
+        pipeline: &wgpu::RenderPipeline,
+        shadow_map_view: &wgpu::TextureView,
+        command_encoder: &mut wgpu::CommandEncoder,
+        idx: u64,
+        stats: &mut RenderStats,
+    ) {
+        stats.shadow_passes += 1;
         let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
             color_attachments: &[],
@@ -527,26 +2290,165 @@ impl Renderer {
             ..Default::default()
         });
 
-        render_pass.set_pipeline(&self.render_pipeline_shadow_map);
+        render_pass.set_pipeline(pipeline);
 
         self.vertex_buffer.set(&mut render_pass);
         self.scene_uniform.set(&mut render_pass, 0, idx);
+        render_pass.set_bind_group(1, &self.empty_bind_group, &[]);
 
-        for Draw {
-            index_start,
-            index_end,
-            base_index,
-            instance_num,
-        } in self.draws.iter()
+        // unskinned draws don't read the primitive uniform here, so a run of them can share
+        // whichever one is bound. skinned draws need their own joint matrices.
+        // points and lines are sorted after the triangles and cast no shadows.
+        let triangle_draws = self
+            .draws
+            .partition_point(|draw| draw.topology == crate::model::Topology::Triangles);
+        let mut first_draw = 0;
+        for run in self.draws[..triangle_draws]
+            .chunk_by(|a, b| a.skinned == b.skinned && (!a.skinned || a.uniform_id == b.uniform_id))
         {
-            render_pass.draw_indexed(
-                *index_start..*index_end,
-                *base_index,
-                *instance_num..*instance_num + 1,
+            self.primitive_uniform
+                .set(&mut render_pass, 2, run[0].uniform_id);
+            self.draw_run(&mut render_pass, first_draw, run, false, stats);
+            first_draw += run.len();
+        }
+    }
+
+    // issues the draws of `run`, which starts at `first_draw` in `self.draws`, with whatever is bound.
+    // `camera` skips the instances outside the camera frustum.
+    fn draw_run(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        first_draw: usize,
+        run: &[Draw],
+        camera: bool,
+        stats: &mut RenderStats,
+    ) {
+        for draw in run {
+            stats.triangles += draw.triangles(camera);
+        }
+        if self.multi_draw_indirect {
+            // the camera's arguments follow the shadows' in the indirect buffer.
+            let first_args = if camera { self.draws.len() } else { 0 } + first_draw;
+            render_pass.multi_draw_indexed_indirect(
+                &self.vertex_buffer.indirect_buffer,
+                (first_args * size_of::<wgpu::util::DrawIndexedIndirectArgs>()) as u64,
+                run.len() as u32,
             );
+            stats.draw_calls += 1;
+        } else {
+            for draw in run {
+                let instances = draw.instances(camera);
+                if instances > 0 {
+                    stats.draw_calls += 1;
+                    render_pass.draw_indexed(
+                        draw.index_start..draw.index_end,
+                        draw.base_index,
+                        draw.instance_num..draw.instance_num + instances,
+                    );
+                }
+            }
+        }
+    }
+
+    // draws the visible opaque and masked batches with the pipelines of `pass`.
+    fn draw_batches(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        pass: OpaquePass,
+        stats: &mut RenderStats,
+    ) {
+        let mut first_draw = 0;
+        for run in self.draws.chunk_by(|a, b| a.uniform_id == b.uniform_id) {
+            let draw = &run[0];
+            // only the lit pass draws points and lines.
+            if draw.alpha_mode != crate::model::AlphaMode::Blend
+                && !draw.transmissive
+                && (pass == OpaquePass::Lit || draw.topology == crate::model::Topology::Triangles)
+                && run.iter().any(|draw| draw.visible_instances > 0)
+            {
+                match pass {
+                    OpaquePass::GBuffer => {}
+                    OpaquePass::Depth => render_pass.set_pipeline(
+                        &self.render_pipelines_depth_prepass[draw.double_sided as usize],
+                    ),
+                    OpaquePass::Lit => self.set_pipeline(render_pass, draw),
+                }
+                self.bind_material(render_pass, draw);
+                self.draw_run(render_pass, first_draw, run, true, stats);
+            }
+            first_draw += run.len();
         }
     }
 
+    // the pass shading the scene into the HDR texture, with the lighting bound.
+    // `clear` starts the frame, otherwise it continues on what's already drawn.
+    fn begin_lit_pass<'a>(
+        &self,
+        command_encoder: &'a mut wgpu::CommandEncoder,
+        clear: bool,
+    ) -> wgpu::RenderPass<'a> {
+        // with MSAA the lit pass renders into the multisampled texture and resolves into the HDR texture.
+        let (color_view, resolve_target) = match &self.multisampled_texture {
+            Some(texture) => (&texture.view, Some(&self.hdr_texture.view)),
+            None => (&self.hdr_texture.view, None),
+        };
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                depth_slice: None,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: if clear {
+                        wgpu::LoadOp::Clear(self.clear_color)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    // reverse-Z, the far plane is at 0.
+                    load: if clear {
+                        wgpu::LoadOp::Clear(0.0)
+                    } else {
+                        wgpu::LoadOp::Load
+                    },
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            ..Default::default()
+        });
+
+        self.vertex_buffer.set(&mut render_pass);
+        self.scene_uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.shadow_map_bind_group, &[]);
+        render_pass
+    }
+
+    /// Renders `scene` into `view` and submits the commands to `queue`.
+    ///
+    /// `view` must have the format and size the renderer was created or last resized with.
+    /// Uploads the scene's transforms first, call `upload_textures` once after loading a scene.
+    pub fn render_to(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &crate::model::Scene,
+        view: &wgpu::TextureView,
+    ) {
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.write_vertex(device, queue, scene);
+        self.render(&mut command_encoder, view, queue, scene);
+        queue.submit(std::iter::once(command_encoder.finish()));
+    }
+
+    // records the frame into `command_encoder`, after `write_vertex` has uploaded the scene.
+    // submit it before the next call, that's when the staging buffers of this frame are reused.
     pub fn render(
         &mut self,
         command_encoder: &mut wgpu::CommandEncoder,
@@ -554,51 +2456,119 @@ impl Renderer {
         queue: &wgpu::Queue,
         scene: &crate::model::Scene,
     ) {
-        for (i, light) in scene.lights.iter().enumerate() {
+        self.staging_belt.recall();
+        let aspect_ratio = self.width as f32 / self.height as f32;
+        let cascade_splits = self.cascade_splits(&scene.camera);
+        let mut cascade_matrices = [Mat4::IDENTITY; SHADOW_MAP_LAYERS as usize];
+        // (layer, light space matrix) of every shadow map to render this frame.
+        let mut shadow_passes = Vec::new();
+        let mut stats = RenderStats {
+            draw_calls: 0,
+            triangles: 0,
+            shadow_passes: 0,
+            ..self.stats
+        };
+
+        let lights = scene.all_lights();
+        for (i, light) in lights.iter().take(MAX_SHADOWED_LIGHTS as usize).enumerate() {
+            let first_layer = i as u32 * CASCADE_COUNT;
+            match light {
+                crate::model::Light::Directional { .. } => {
+                    for cascade in 0..CASCADE_COUNT {
+                        let corners = scene.camera.frustum_corners(
+                            aspect_ratio,
+                            cascade_splits[cascade as usize],
+                            cascade_splits[cascade as usize + 1],
+                        );
+                        let layer = first_layer + cascade;
+                        let matrix =
+                            light.cascade_matrix(&corners, self.shadow_distance, SHADOW_MAP_SIZE);
+                        cascade_matrices[layer as usize] = matrix;
+                        shadow_passes.push((layer, matrix));
+                    }
+                }
+                crate::model::Light::Spot { .. } => {
+                    shadow_passes.push((first_layer, light.matrix()))
+                }
+                crate::model::Light::Point { pos, .. } => {
+                    let matrices = light.cube_face_matrices(self.shadow_distance);
+                    for (face, matrix) in matrices.into_iter().enumerate() {
+                        let layer = i as u32 * 6 + face as u32;
+                        let idx = (1 + SHADOW_MAP_LAYERS + layer) as u64;
+                        self.write_shadow_map_uniform(command_encoder, matrix, *pos, idx);
+                        self.render_shadow_map(
+                            &self.render_pipeline_point_shadow_map,
+                            &self.point_shadow_map_views[layer as usize],
+                            command_encoder,
+                            idx,
+                            &mut stats,
+                        );
+                    }
+                }
+            }
+        }
+
+        for &(layer, matrix) in shadow_passes.iter() {
+            self.write_shadow_map_uniform(command_encoder, matrix, Vec3::ZERO, layer as u64 + 1);
             self.render_shadow_map(
-                queue,
-                &self.shadow_map_views[i],
+                &self.render_pipeline_shadow_map,
+                &self.shadow_map_views[layer as usize],
                 command_encoder,
-                light,
-                i as u64 + 1,
+                layer as u64 + 1,
+                &mut stats,
             );
         }
 
-        let aspect_ratio = self.width as f32 / self.height as f32;
         let camera_matrix = scene.camera.get_matrix(aspect_ratio);
 
-        let lights: Vec<crate::model::LightRaw> =
-            scene.lights.iter().map(|light| light.raw()).collect();
-        self.scene_uniform.write(
-            queue,
+        let raw_lights: Vec<crate::model::LightRaw> =
+            lights.iter().map(|light| light.raw()).collect();
+        if let Some(size) = wgpu::BufferSize::new(size_of_val(raw_lights.as_slice()) as u64) {
+            self.staging_belt
+                .write_buffer(command_encoder, &self.light_buffer, 0, size)
+                .copy_from_slice(bytemuck::cast_slice(&raw_lights));
+        }
+        self.scene_uniform.write_staged(
+            &mut self.staging_belt,
+            command_encoder,
             0,
             &[
                 bytemuck::cast_slice(&[camera_matrix]),
-                bytemuck::cast_slice(&[scene.camera.position]),
-                bytemuck::cast_slice(&lights),
+                bytemuck::cast_slice(&[scene.camera.eye()]),
+                bytemuck::cast_slice(&[self.light_culling.tile_columns()]),
+                bytemuck::cast_slice(&[scene.camera.direction()]),
+                bytemuck::cast_slice(&[self.pcf_radius]),
+                bytemuck::cast_slice(&cascade_splits[1..]),
+                bytemuck::cast_slice(&cascade_matrices),
+                bytemuck::cast_slice(&[self.shadow_distance]),
+                bytemuck::cast_slice(&[Vec2::new(self.shadow_bias, self.shadow_normal_bias)]),
+                bytemuck::cast_slice(&[self.debug_view as u32]),
             ],
         );
 
-        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: output_view,
+        let [position_view, normal_view, prepass_depth_view] = self.ssao.gbuffer_views();
+        let gbuffer_attachment = |view| {
+            Some(wgpu::RenderPassColorAttachment {
+                view,
                 depth_slice: None,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: 1.0,
-                    }),
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
                     store: wgpu::StoreOp::Store,
                 },
-            })],
+            })
+        };
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Prepass"),
+            color_attachments: &[
+                gbuffer_attachment(position_view),
+                gbuffer_attachment(normal_view),
+            ],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_texture.view,
+                view: prepass_depth_view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
+                    // reverse-Z, the far plane is at 0.
+                    load: wgpu::LoadOp::Clear(0.0),
                     store: wgpu::StoreOp::Store,
                 }),
                 stencil_ops: None,
@@ -606,33 +2576,389 @@ impl Renderer {
             ..Default::default()
         });
 
-        render_pass.set_pipeline(&self.render_pipeline);
-
+        render_pass.set_pipeline(&self.render_pipeline_prepass);
         self.vertex_buffer.set(&mut render_pass);
         self.scene_uniform.set(&mut render_pass, 0, 0);
         render_pass.set_bind_group(1, &self.shadow_map_bind_group, &[]);
+        self.draw_batches(&mut render_pass, OpaquePass::GBuffer, &mut stats);
+        drop(render_pass);
+
+        self.light_culling.render(
+            command_encoder,
+            queue,
+            &scene.camera,
+            camera_matrix,
+            lights.len() as u32,
+            self.light_cutoff,
+        );
+
+        self.ssao.render(
+            command_encoder,
+            queue,
+            &scene.camera,
+            camera_matrix,
+            self.ssao_radius,
+            self.ssao_strength,
+        );
+        self.skybox
+            .write(queue, camera_matrix, self.sky_gradient.as_ref());
+        if self.grid_visible {
+            self.grid
+                .write(queue, camera_matrix, self.grid_spacing, self.grid_color);
+        }
+
+        let mut render_pass = self.begin_lit_pass(command_encoder, true);
+
+        if self.depth_prepass {
+            self.draw_batches(&mut render_pass, OpaquePass::Depth, &mut stats);
+        }
+        self.draw_batches(&mut render_pass, OpaquePass::Lit, &mut stats);
 
-        for Draw {
-            index_start,
-            index_end,
-            base_index,
-            instance_num,
-        } in self.draws.iter()
+        // behind the opaque primitives, but under the transparent ones blended onto it.
+        if self
+            .skybox
+            .draw(&mut render_pass, self.sky_gradient.is_some())
         {
-            self.primitive_uniform
-                .set(&mut render_pass, 2, *instance_num as u64);
+            self.scene_uniform.set(&mut render_pass, 0, 0);
+            render_pass.set_bind_group(1, &self.shadow_map_bind_group, &[]);
+        }
+        if self.grid_visible {
+            self.grid.draw(&mut render_pass);
+            self.scene_uniform.set(&mut render_pass, 0, 0);
+        }
+
+        // transparent primitives are blended back to front, transmissive ones are drawn in between
+        // over a copy of everything drawn before them.
+        let mut sorted_draws: Vec<&Draw> = self
+            .draws
+            .iter()
+            .filter(|draw| {
+                draw.visible_instances > 0
+                    && (draw.alpha_mode == crate::model::AlphaMode::Blend || draw.transmissive)
+            })
+            .collect();
+        let camera_distance = |draw: &Draw| {
+            scene.meshes[draw.meshes[0]]
+                .transform
+                .matrix()
+                .transform_point3(draw.center)
+                .distance_squared(scene.camera.eye())
+        };
+        sorted_draws.sort_by(|a, b| camera_distance(b).total_cmp(&camera_distance(a)));
+        if sorted_draws.iter().any(|draw| draw.transmissive) {
+            drop(render_pass);
+            self.transmission.capture(command_encoder);
+            render_pass = self.begin_lit_pass(command_encoder, false);
+        }
+
+        for draw in sorted_draws {
+            stats.draw_calls += 1;
+            stats.triangles += draw.triangles(true);
+            self.set_pipeline(&mut render_pass, draw);
+            self.bind_material(&mut render_pass, draw);
             render_pass.draw_indexed(
-                *index_start..*index_end,
-                *base_index,
-                *instance_num..*instance_num + 1,
+                draw.index_start..draw.index_end,
+                draw.base_index,
+                draw.instance_num..draw.instance_num + 1,
+            );
+        }
+        if self.show_light_gizmos {
+            self.light_gizmos.draw(&mut render_pass);
+        }
+        drop(render_pass);
+        self.staging_belt.finish();
+
+        self.bloom
+            .render(command_encoder, queue, self.bloom_threshold);
+        // FXAA runs on the tone mapped image, so tone mapping writes to its input instead.
+        if self.fxaa_enabled {
+            self.render_tone_map(command_encoder, self.fxaa.view(), queue);
+            self.fxaa.render(command_encoder, output_view);
+        } else {
+            self.render_tone_map(command_encoder, output_view, queue);
+        }
+        if !self.highlighted_instances.is_empty() {
+            self.render_outline(command_encoder, output_view, &mut stats);
+        }
+        self.stats = stats;
+
+        if let Some(light) = self.debug_view.shadow_map() {
+            let tile_size = self.width.min(self.height) as f32;
+            self.render_shadow_map_overlay(
+                command_encoder,
+                output_view,
+                &[light * CASCADE_COUNT],
+                tile_size,
+            );
+        } else if self.show_shadow_maps {
+            let layers: Vec<u32> = shadow_passes.iter().map(|(layer, _)| *layer).collect();
+            let tile_size = (self.height / 4).min(self.width / layers.len().max(1) as u32) as f32;
+            self.render_shadow_map_overlay(command_encoder, output_view, &layers, tile_size);
+        }
+    }
+
+    // view depths bounding each cascade, from the camera's near plane to `shadow_distance`.
+    // blends logarithmic and uniform splits so near cascades stay small without starving far ones.
+    fn cascade_splits(&self, camera: &crate::model::Camera) -> [f32; CASCADE_COUNT as usize + 1] {
+        const LAMBDA: f32 = 0.5;
+        let near = camera.znear;
+        let far = camera
+            .zfar
+            .unwrap_or(f32::INFINITY)
+            .min(self.shadow_distance)
+            .max(near);
+        let mut splits = [near; CASCADE_COUNT as usize + 1];
+        for (i, split) in splits.iter_mut().enumerate() {
+            let t = i as f32 / CASCADE_COUNT as f32;
+            let logarithmic = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            *split = LAMBDA * logarithmic + (1.0 - LAMBDA) * uniform;
+        }
+        splits
+    }
+
+    fn set_pipeline(&self, render_pass: &mut wgpu::RenderPass, draw: &Draw) {
+        let transparent = draw.alpha_mode == crate::model::AlphaMode::Blend;
+        let key = PipelineKey {
+            topology: draw.topology,
+            transparent,
+            double_sided: draw.double_sided,
+            depth_prepass: self.depth_prepass
+                && !transparent
+                && draw.topology == crate::model::Topology::Triangles,
+        };
+        render_pass.set_pipeline(&self.render_pipelines[&key]);
+    }
+
+    // binds the primitive uniform and textures of the draw's batch.
+    fn bind_material(&self, render_pass: &mut wgpu::RenderPass, draw: &Draw) {
+        self.primitive_uniform.set(render_pass, 2, draw.uniform_id);
+        render_pass.set_bind_group(
+            3,
+            draw.material_index
+                .filter(|_| self.material_override.is_none())
+                .and_then(|index| self.material_texture_bind_groups.get(&index))
+                .unwrap_or(&self.default_material_texture_bind_group),
+            &[],
+        );
+    }
+
+    // maps the HDR texture into the output.
+    fn render_tone_map(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        output_view: &wgpu::TextureView,
+        queue: &wgpu::Queue,
+    ) {
+        // the surface debug views show their values as they are.
+        let (exposure, tone_mapping, bloom_intensity) = if self.debug_view.is_surface() {
+            (1.0, TONE_MAPPING_NONE, 0.0)
+        } else {
+            (
+                self.exposure,
+                self.tone_mapping as u32,
+                self.bloom_intensity,
+            )
+        };
+        self.tone_map_uniform.write(
+            queue,
+            0,
+            &[
+                bytemuck::cast_slice(&[exposure]),
+                bytemuck::cast_slice(&[tone_mapping]),
+                bytemuck::cast_slice(&[bloom_intensity]),
+            ],
+        );
+
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Tone map"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        render_pass.set_pipeline(&self.render_pipeline_tone_map);
+        self.tone_map_uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.hdr_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    // outlines the highlighted mesh over the tone mapped `output_view`, also where it's hidden
+    // behind other meshes.
+    fn render_outline(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        output_view: &wgpu::TextureView,
+        stats: &mut RenderStats,
+    ) {
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Outline"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.outline_stencil.view,
+                depth_ops: None,
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+            }),
+            ..Default::default()
+        });
+        render_pass.set_stencil_reference(1);
+        self.vertex_buffer.set(&mut render_pass);
+        self.scene_uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.empty_bind_group, &[]);
+
+        // the whole mask goes first, so the outline of one primitive doesn't cover another.
+        for pipeline in [
+            &self.render_pipeline_outline_mask,
+            &self.render_pipeline_outline,
+        ] {
+            render_pass.set_pipeline(pipeline);
+            for &(draw, instance) in self.highlighted_instances.iter() {
+                let draw = &self.draws[draw];
+                self.primitive_uniform
+                    .set(&mut render_pass, 2, draw.uniform_id);
+                render_pass.draw_indexed(
+                    draw.index_start..draw.index_end,
+                    draw.base_index,
+                    instance..instance + 1,
+                );
+                stats.draw_calls += 1;
+                stats.triangles += (draw.index_end - draw.index_start) as u64 / 3;
+            }
+        }
+    }
+
+    // draws shadow map layers as `tile_size` squares along the bottom of the lit output.
+    fn render_shadow_map_overlay(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        output_view: &wgpu::TextureView,
+        layers: &[u32],
+        tile_size: f32,
+    ) {
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow map overlay"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        render_pass.set_pipeline(&self.render_pipeline_full);
+        self.scene_uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.shadow_map_bind_group, &[]);
+
+        for (i, &layer) in layers.iter().enumerate() {
+            render_pass.set_viewport(
+                i as f32 * tile_size,
+                self.height as f32 - tile_size,
+                tile_size,
+                tile_size,
+                0.0,
+                1.0,
             );
+            render_pass.draw(0..3, layer..layer + 1);
         }
     }
 }
 
-pub struct Draw {
+pub(crate) struct Draw {
     pub index_start: u32,
     pub index_end: u32,
     pub base_index: i32,
+    // first of its instances, which are contiguous in the instance buffer.
     pub instance_num: u32,
+    pub instance_count: u32,
+    // instances intersecting the camera frustum, which come first.
+    // culled instances are only skipped in the camera's passes.
+    pub visible_instances: u32,
+    // indices into `Scene::meshes` of the meshes placing its instances.
+    pub meshes: Vec<usize>,
+    // of the primitive, in its meshes' space.
+    pub aabb_min: Vec3,
+    pub aabb_max: Vec3,
+    // the primitive uniform, shared by every draw in the batch.
+    pub uniform_id: u64,
+    pub material_index: Option<usize>,
+    pub alpha_mode: crate::model::AlphaMode,
+    pub double_sided: bool,
+    pub topology: crate::model::Topology,
+    // of the primitive, placed by its first mesh to sort transparent and transmissive draws,
+    // which have a single instance.
+    pub center: Vec3,
+    // reads its joint matrices from its primitive uniform even where the material isn't needed.
+    pub skinned: bool,
+    // samples the scene behind it, so it's drawn after the transmission pass copied that.
+    pub transmissive: bool,
+}
+
+impl Draw {
+    // how many instances the camera's passes (`camera`) or the shadow passes draw.
+    fn instances(&self, camera: bool) -> u32 {
+        if camera {
+            self.visible_instances
+        } else {
+            self.instance_count
+        }
+    }
+
+    // triangles of the instances the camera's passes or the shadow passes draw.
+    fn triangles(&self, camera: bool) -> u64 {
+        match self.topology {
+            crate::model::Topology::Triangles => {
+                (self.index_end - self.index_start) as u64 / 3 * self.instances(camera) as u64
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grown_size_doubles() {
+        let vertex_size = std::mem::size_of::<Vertex>() as u64;
+        let mut size = 0;
+        let mut reallocations = 0;
+        for vertex_count in 1..=10_000 {
+            let needed = vertex_count * vertex_size;
+            let grown = grown_size(size, needed);
+            assert!(grown >= needed);
+            assert!(grown >= size);
+            assert!(grown < 2 * needed || grown == size);
+            if grown != size {
+                reallocations += 1;
+            }
+            size = grown;
+        }
+        // 1 to 10000 vertices is less than 15 doublings.
+        assert!(reallocations <= 15);
+        // shrinking the scene keeps the buffer.
+        assert_eq!(grown_size(size, vertex_size), size);
+    }
 }
@@ -0,0 +1,42 @@
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use pbrpbr::model::{Scene, SceneError};
+
+// loads a scene on a thread of its own, so the window keeps responding while big files are
+// parsed and their images decoded. the textures are still uploaded on the render thread.
+pub(crate) struct SceneLoader {
+    result: Receiver<Result<Scene, SceneError>>,
+    // replaces a scene already shown, rather than the empty one at startup.
+    pub reload: bool,
+}
+
+impl SceneLoader {
+    pub fn new(path: &str, reload: bool) -> Self {
+        let path = path.to_owned();
+        let (sender, result) = channel();
+        std::thread::spawn(move || {
+            // the loader may have been replaced or dropped, then nobody wants this scene.
+            let _ = sender.send(Scene::load(&path));
+        });
+        Self { result, reload }
+    }
+
+    // the loaded scene or why it failed, once it's done. None while it's still loading.
+    pub fn poll(&self) -> Option<Result<Scene, SceneError>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            // the thread is gone without sending anything.
+            Err(TryRecvError::Disconnected) => Some(Err(SceneError::Io(std::io::Error::other(
+                "the scene loader thread panicked",
+            )))),
+        }
+    }
+
+    // blocks until the scene is loaded.
+    pub fn wait(self) -> Result<Scene, SceneError> {
+        self.result
+            .recv()
+            .expect("the scene loader thread panicked")
+    }
+}
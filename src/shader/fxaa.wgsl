@@ -0,0 +1,120 @@
+// tone mapped color. the texture is sRGB, so samples come back linear.
+@group(0) @binding(0)
+var color_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var color_sampler: sampler;
+
+// skip pixels whose local contrast is below max(EDGE_THRESHOLD_MIN, EDGE_THRESHOLD * brightest luma).
+const EDGE_THRESHOLD:f32 = 0.125;
+const EDGE_THRESHOLD_MIN:f32 = 0.0312;
+const SEARCH_STEPS:u32 = 8;
+// how much sub-pixel aliasing (thin lines, single bright pixels) is blurred away.
+const SUBPIXEL_QUALITY:f32 = 0.75;
+
+// full-screen triangle, same as `vs_full` in shader.wgsl.
+@vertex
+fn vs_full(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4f {
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    return vec4f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0), 0.0, 1.0);
+}
+
+// finds edges from luma contrast, walks along them to their ends and
+// shifts the sample across the edge by how close the pixel is to an end.
+@fragment
+fn fs_fxaa(
+    @builtin(position) position: vec4f,
+) -> @location(0) vec4f {
+    let texel = 1.0 / vec2f(textureDimensions(color_texture));
+    let uv = position.xy * texel;
+
+    let center = textureSampleLevel(color_texture, color_sampler, uv, 0.0);
+    let luma_m = luma(center.rgb);
+    let luma_n = luma_at(uv, texel, vec2f(0.0, -1.0));
+    let luma_s = luma_at(uv, texel, vec2f(0.0, 1.0));
+    let luma_w = luma_at(uv, texel, vec2f(-1.0, 0.0));
+    let luma_e = luma_at(uv, texel, vec2f(1.0, 0.0));
+
+    let luma_min = min(luma_m, min(min(luma_n, luma_s), min(luma_w, luma_e)));
+    let luma_max = max(luma_m, max(max(luma_n, luma_s), max(luma_w, luma_e)));
+    let luma_range = luma_max - luma_min;
+    if luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD) {
+        return center;
+    }
+
+    let luma_nw = luma_at(uv, texel, vec2f(-1.0, -1.0));
+    let luma_ne = luma_at(uv, texel, vec2f(1.0, -1.0));
+    let luma_sw = luma_at(uv, texel, vec2f(-1.0, 1.0));
+    let luma_se = luma_at(uv, texel, vec2f(1.0, 1.0));
+
+    // a horizontal edge changes most along y.
+    let horizontal = 2.0 * abs(luma_n + luma_s - 2.0 * luma_m)
+        + abs(luma_ne + luma_se - 2.0 * luma_e)
+        + abs(luma_nw + luma_sw - 2.0 * luma_w);
+    let vertical = 2.0 * abs(luma_w + luma_e - 2.0 * luma_m)
+        + abs(luma_nw + luma_ne - 2.0 * luma_n)
+        + abs(luma_sw + luma_se - 2.0 * luma_s);
+    let is_horizontal = horizontal >= vertical;
+
+    // which side of the pixel the edge lies on.
+    let luma_negative = select(luma_w, luma_n, is_horizontal);
+    let luma_positive = select(luma_e, luma_s, is_horizontal);
+    let gradient_negative = abs(luma_negative - luma_m);
+    let gradient_positive = abs(luma_positive - luma_m);
+    let is_negative = gradient_negative >= gradient_positive;
+    let gradient_scaled = 0.25 * max(gradient_negative, gradient_positive);
+    let step_length = select(1.0, -1.0, is_negative) * select(texel.x, texel.y, is_horizontal);
+    let luma_edge = 0.5 * (luma_m + select(luma_positive, luma_negative, is_negative));
+
+    // walk both ways along the edge, halfway between this pixel and its neighbor across the edge.
+    let across = select(vec2f(step_length * 0.5, 0.0), vec2f(0.0, step_length * 0.5), is_horizontal);
+    let along = select(vec2f(0.0, texel.y), vec2f(texel.x, 0.0), is_horizontal);
+    let edge_uv = uv + across;
+    var uv1 = edge_uv - along;
+    var uv2 = edge_uv + along;
+    var end1 = luma(textureSampleLevel(color_texture, color_sampler, uv1, 0.0).rgb) - luma_edge;
+    var end2 = luma(textureSampleLevel(color_texture, color_sampler, uv2, 0.0).rgb) - luma_edge;
+    var reached1 = abs(end1) >= gradient_scaled;
+    var reached2 = abs(end2) >= gradient_scaled;
+    var steps = array<f32, SEARCH_STEPS>(1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0, 8.0);
+    for(var i = 0u; i < SEARCH_STEPS && !(reached1 && reached2); i++) {
+        if !reached1 {
+            uv1 -= along * steps[i];
+            end1 = luma(textureSampleLevel(color_texture, color_sampler, uv1, 0.0).rgb) - luma_edge;
+            reached1 = abs(end1) >= gradient_scaled;
+        }
+        if !reached2 {
+            uv2 += along * steps[i];
+            end2 = luma(textureSampleLevel(color_texture, color_sampler, uv2, 0.0).rgb) - luma_edge;
+            reached2 = abs(end2) >= gradient_scaled;
+        }
+    }
+
+    let distance1 = select(uv.y - uv1.y, uv.x - uv1.x, is_horizontal);
+    let distance2 = select(uv2.y - uv.y, uv2.x - uv.x, is_horizontal);
+    let is_closer_to_1 = distance1 < distance2;
+    let distance = min(distance1, distance2);
+    let pixel_offset = 0.5 - distance / (distance1 + distance2);
+
+    // only blend if the closer end moves away from this pixel's luma, otherwise we're on the wrong side of the edge.
+    let end = select(end2, end1, is_closer_to_1);
+    let correct_variation = (end < 0.0) != (luma_m < luma_edge);
+    var offset = select(0.0, pixel_offset, correct_variation);
+
+    let luma_average = (2.0 * (luma_n + luma_s + luma_w + luma_e) + luma_nw + luma_ne + luma_sw + luma_se) / 12.0;
+    let subpixel = smoothstep(0.0, 1.0, clamp(abs(luma_average - luma_m) / luma_range, 0.0, 1.0));
+    offset = max(offset, subpixel * subpixel * SUBPIXEL_QUALITY);
+
+    let final_uv = uv + select(vec2f(offset * step_length, 0.0), vec2f(0.0, offset * step_length), is_horizontal);
+    return vec4f(textureSampleLevel(color_texture, color_sampler, final_uv, 0.0).rgb, center.a);
+}
+
+fn luma_at(uv: vec2f, texel: vec2f, offset: vec2f) -> f32 {
+    return luma(textureSampleLevel(color_texture, color_sampler, uv + offset * texel, 0.0).rgb);
+}
+
+// perceptual luma. sqrt is close enough to the sRGB curve for edge detection.
+fn luma(linear: vec3f) -> f32 {
+    return sqrt(dot(linear, vec3f(0.299, 0.587, 0.114)));
+}
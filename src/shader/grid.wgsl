@@ -0,0 +1,66 @@
+@group(0) @binding(0)
+var<uniform> camera_matrix: mat4x4f;
+@group(0) @binding(1)
+var<uniform> inverse_camera_matrix: mat4x4f;
+@group(0) @binding(2)
+var<uniform> color: vec3f;
+// world space distance between lines, and distance from the camera where they're faded out.
+@group(0) @binding(3)
+var<uniform> spacing_fade: vec2f;
+
+struct VertexOutput {
+    @builtin(position) position: vec4f,
+    @location(0) ndc: vec2f,
+}
+
+struct FragmentOutput {
+    @builtin(frag_depth) depth: f32,
+    @location(0) color: vec4f,
+}
+
+// full-screen triangle, same as `vs_sky` in skybox.wgsl. the depth comes from the plane instead.
+@vertex
+fn vs_grid(
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    let ndc = vec2f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0));
+    var out: VertexOutput;
+    out.position = vec4f(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_grid(in: VertexOutput) -> FragmentOutput {
+    // the far plane may be at infinity, so the ray goes through two nearer points instead.
+    let near = unproject(vec3f(in.ndc, 1.0));
+    let ray = unproject(vec3f(in.ndc, 0.5)) - near;
+    // where the camera ray hits y = 0. negative when it points away from the plane.
+    let t = -near.y / ray.y;
+    let position = near + ray * max(t, 0.0);
+
+    // distance to the nearest line in pixels, from the screen space rate of change of the cell coordinate.
+    // computed before anything is discarded, derivatives need every pixel of the quad.
+    let cell = position.xz / spacing_fade.x;
+    let pixels = abs(fract(cell - 0.5) - 0.5) / max(fwidth(cell), vec2f(1e-6));
+    let line = 1.0 - min(min(pixels.x, pixels.y), 1.0);
+
+    let clip = camera_matrix * vec4f(position, 1.0);
+    let depth = clip.z / clip.w;
+    // reverse-Z, behind the far plane below 0.
+    if t <= 0.0 || depth <= 0.0 {
+        discard;
+    }
+
+    let fade = 1.0 - smoothstep(0.0, spacing_fade.y, distance(position, near));
+    var out: FragmentOutput;
+    out.depth = depth;
+    out.color = vec4f(color, line * fade);
+    return out;
+}
+
+fn unproject(ndc: vec3f) -> vec3f {
+    let position = inverse_camera_matrix * vec4f(ndc, 1.0);
+    return position.xyz / position.w;
+}
@@ -0,0 +1,152 @@
+// must match `TILE_SIZE` and `MAX_LIGHTS_PER_TILE` in light_culling.rs
+const TILE_SIZE: u32 = 16;
+const MAX_LIGHTS_PER_TILE: u32 = 63;
+
+// same as in shader.wgsl
+const LIGHT_POINT:u32 = 1;
+const LIGHT_DIRECTIONAL:u32 = 2;
+const LIGHT_SPOT:u32 = 3;
+
+// same as in shader.wgsl
+struct Light {
+    matrix: mat4x4f,
+    pos: vec3f,
+    typ: u32,
+    color: vec3f,
+    intensity: f32,
+    direction: vec3f,
+    extra1: f32,
+    extra2: f32,
+}
+
+// maps clip space to positions relative to the camera, like the G-buffer's.
+@group(0) @binding(0)
+var<uniform> inverse_camera: mat4x4f;
+@group(0) @binding(1)
+var<uniform> camera_pos: vec3f;
+@group(0) @binding(2)
+var<uniform> camera_dir: vec3f;
+@group(0) @binding(3)
+var<uniform> light_count: u32;
+// lights don't reach where their irradiance falls below this.
+@group(0) @binding(4)
+var<uniform> light_cutoff: f32;
+
+// camera relative position written by the prepass, alpha is 0 where nothing was drawn.
+@group(1) @binding(0)
+var position: texture_2d<f32>;
+@group(1) @binding(1)
+var<storage, read> lights: array<Light>;
+// per tile, the number of lights followed by `MAX_LIGHTS_PER_TILE` light indices.
+@group(1) @binding(2)
+var<storage, read_write> tile_lights: array<u32>;
+
+const F32_MAX: f32 = 3.40282347e+38;
+
+// bits of the largest view depth in the tile. non-negative floats order like their bits.
+var<workgroup> max_depth: atomic<u32>;
+var<workgroup> tile_light_count: atomic<u32>;
+
+// one workgroup per tile, one invocation per pixel.
+@compute @workgroup_size(TILE_SIZE, TILE_SIZE)
+fn cs_cull(
+    @builtin(workgroup_id) tile: vec3u,
+    @builtin(num_workgroups) tile_count: vec3u,
+    @builtin(local_invocation_id) local_id: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if local_index == 0u {
+        atomicStore(&max_depth, 0u);
+        atomicStore(&tile_light_count, 0u);
+    }
+    workgroupBarrier();
+
+    let size = textureDimensions(position);
+    let pixel = tile.xy * TILE_SIZE + local_id.xy;
+    if all(pixel < size) {
+        let texel = textureLoad(position, pixel, 0);
+        // pixels without geometry see arbitrarily far.
+        let depth = select(F32_MAX, max(dot(texel.xyz, camera_dir), 0.0), texel.a > 0.0);
+        atomicMax(&max_depth, bitcast<u32>(depth));
+    }
+    workgroupBarrier();
+    let tile_max_depth = bitcast<f32>(atomicLoad(&max_depth));
+
+    // the planes through the tile's sides, facing inwards.
+    let uv_min = vec2f(tile.xy * TILE_SIZE) / vec2f(size);
+    let uv_max = vec2f((tile.xy + 1u) * TILE_SIZE) / vec2f(size);
+    let inside = unproject(0.5 * (uv_min + uv_max), 0.5);
+    let planes = array(
+        side_plane(uv_min, vec2f(uv_min.x, uv_max.y), inside),
+        side_plane(vec2f(uv_max.x, uv_min.y), uv_max, inside),
+        side_plane(uv_min, vec2f(uv_max.x, uv_min.y), inside),
+        side_plane(vec2f(uv_min.x, uv_max.y), uv_max, inside),
+    );
+
+    for (var first = 0u; first < light_count; first += TILE_SIZE * TILE_SIZE) {
+        let i = first + local_index;
+        if i >= light_count {
+            break;
+        }
+        let light = lights[i];
+
+        var visible = true;
+        // directional lights reach every tile.
+        if light.typ != LIGHT_DIRECTIONAL {
+            let pos = light.pos - camera_pos;
+            let range = light_range(light);
+            let depth = dot(pos, camera_dir);
+            visible = depth + range > 0.0 && depth - range < tile_max_depth;
+            for (var plane = 0; plane < 4; plane++) {
+                visible = visible && dot(planes[plane].xyz, pos) + planes[plane].w > -range;
+            }
+        }
+
+        if visible {
+            let slot = atomicAdd(&tile_light_count, 1u);
+            if slot < MAX_LIGHTS_PER_TILE {
+                tile_lights[tile_offset(tile.xy, tile_count.x) + 1u + slot] = i;
+            }
+        }
+    }
+    workgroupBarrier();
+
+    if local_index == 0u {
+        tile_lights[tile_offset(tile.xy, tile_count.x)] = min(atomicLoad(&tile_light_count), MAX_LIGHTS_PER_TILE);
+    }
+}
+
+fn tile_offset(tile: vec2u, tile_columns: u32) -> u32 {
+    return (tile.y * tile_columns + tile.x) * (MAX_LIGHTS_PER_TILE + 1u);
+}
+
+// camera relative position at a screen uv and clip space depth.
+fn unproject(uv: vec2f, depth: f32) -> vec3f {
+    let pos = inverse_camera * vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    return pos.xyz / pos.w;
+}
+
+// the plane through the screen space edge from `a` to `b`, facing `inside`.
+// it goes through points at two depths so that it works for orthographic cameras too.
+fn side_plane(a: vec2f, b: vec2f, inside: vec3f) -> vec4f {
+    let p0 = unproject(a, 0.25);
+    let p1 = unproject(b, 0.25);
+    let p2 = unproject(a, 0.5);
+    var normal = normalize(cross(p1 - p0, p2 - p0));
+    if dot(normal, inside - p0) < 0.0 {
+        normal = -normal;
+    }
+    return vec4f(normal, -dot(normal, p0));
+}
+
+// distance at which the light's irradiance falls to `light_cutoff`. must match the falloff in shader.wgsl.
+fn light_range(light: Light) -> f32 {
+    switch light.typ {
+        case LIGHT_SPOT: {
+            return sqrt(0.2 * light.intensity / light_cutoff);
+        }
+        default: {
+            return sqrt(light.intensity / light_cutoff);
+        }
+    }
+}
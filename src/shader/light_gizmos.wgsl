@@ -0,0 +1,23 @@
+@group(0) @binding(0)
+var<uniform> camera: mat4x4f;
+
+struct VertexOutput {
+    @builtin(position) position: vec4f,
+    @location(0) color: vec3f,
+}
+
+@vertex
+fn vs_gizmo(
+    @location(0) position: vec3f,
+    @location(1) color: vec3f,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = camera * vec4f(position, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_gizmo(in: VertexOutput) -> @location(0) vec4f {
+    return vec4f(in.color, 1.0);
+}
@@ -0,0 +1,28 @@
+// the mip level above the one being rendered.
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+// full-screen triangle, same as `vs_full` in shader.wgsl but also passing the uv.
+@vertex
+fn vs_full(
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    var out: VertexOutput;
+    out.position = vec4f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// a bilinear sample at the texel corner averages the 2x2 block below it.
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4f {
+    return textureSampleLevel(source_texture, source_sampler, in.uv, 0.0);
+}
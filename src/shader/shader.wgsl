@@ -10,12 +10,22 @@ struct VertexInput {
     @location(6) rot_0: vec3f,
     @location(7) rot_1: vec3f,
     @location(8) rot_2: vec3f,
+
+    @location(9) uv: vec2f,
+    @location(10) tangent: vec4f,
+    @location(11) color: vec4f,
+    @location(12) joints: vec4u,
+    @location(13) weights: vec4f,
 }
 
 struct VertexOutput {
-    @builtin(position) position: vec4f,
+    // the depth prepass and the lit pass must agree on depth exactly.
+    @builtin(position) @invariant position: vec4f,
     @location(0) normal: vec3f,
     @location(1) world_pos: vec3f,
+    @location(2) uv: vec2f,
+    @location(3) tangent: vec4f,
+    @location(4) color: vec4f,
 }
 
 @group(0) @binding(0)
@@ -24,8 +34,9 @@ var<uniform> camera: mat4x4f;
 var<uniform> camera_pos: vec3f;
 
 
-const LIGHT_DIRECTIONAL:u32 = 1;
-const LIGHT_POINT:u32 = 2;
+// must match `typ` written by `Light::raw`
+const LIGHT_POINT:u32 = 1;
+const LIGHT_DIRECTIONAL:u32 = 2;
 const LIGHT_SPOT:u32 = 3;
 
 const PI:f32 = 3.14159265;
@@ -46,88 +57,267 @@ struct Material {
     base_color: vec4f,
     metallic: f32,
     roughness: f32,
+    normal_scale: f32,
+    alpha_mode: u32,
+    alpha_cutoff: f32,
+    occlusion_strength: f32,
+    flags: u32,
+    // KHR_texture_transform, applied to the UVs of every material texture.
+    uv_transform: mat3x3f,
+    // emitted radiance, scaled by the emissive texture.
+    emissive: vec3f,
+    // KHR_materials_clearcoat, used with MATERIAL_CLEARCOAT.
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    clearcoat_normal_scale: f32,
+    // KHR_materials_transmission, used with MATERIAL_TRANSMISSION.
+    transmission: f32,
 }
 
+// must match `Material::UNLIT`
+const MATERIAL_UNLIT:u32 = 1;
+// must match `Material::CLEARCOAT`
+const MATERIAL_CLEARCOAT:u32 = 2;
+// must match `Material::TRANSMISSION`
+const MATERIAL_TRANSMISSION:u32 = 4;
+
+// must match `AlphaMode`
+const ALPHA_OPAQUE:u32 = 0;
+const ALPHA_MASK:u32 = 1;
+const ALPHA_BLEND:u32 = 2;
+
 
 @vertex
 fn vs_main(
     in: VertexInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    let model = mat4x4f(in.model_0, in.model_1, in.model_2, in.model_3);
+    let model = model_matrix(in);
     let world_pos = model * vec4f(in.position, 1.0);
     out.position = camera * world_pos;
     out.world_pos = world_pos.xyz;
+    out.uv = in.uv;
+    out.color = in.color;
 
-    let rot = mat3x3f(in.rot_0, in.rot_1, in.rot_2);
+    var rot = mat3x3f(in.rot_0, in.rot_1, in.rot_2);
+    if is_skinned(in) {
+        // joints are rarely scaled non-uniformly, so the skin matrix stands in for the normal matrix.
+        rot = mat3x3f(model[0].xyz, model[1].xyz, model[2].xyz);
+    }
     out.normal = normalize(rot * in.normal);
+    // tangents follow the surface, so they use the model matrix rather than the normal matrix.
+    let tangent = (model * vec4f(in.tangent.xyz, 0.0)).xyz;
+    out.tangent = vec4f(select(vec3f(0.0), normalize(tangent), length(tangent) > 0.0), in.tangent.w);
     return out;
 }
 
+// tiles per row, see `tile_lights`.
 @group(0) @binding(2)
-var<uniform> lights: array<Light, 4>;
+var<uniform> tile_columns: u32;
+@group(0) @binding(3)
+var<uniform> camera_dir: vec3f;
+@group(0) @binding(4)
+var<uniform> pcf_radius: u32;
+// view depth where each directional light cascade ends.
+@group(0) @binding(5)
+var<uniform> cascade_splits: vec4f;
+// indexed by shadow map layer. only the directional light's layers are filled.
+@group(0) @binding(6)
+var<uniform> cascade_matrices: array<mat4x4f, SHADOW_MAP_LAYERS>;
+// point light shadow maps store distance from the light divided by this.
+@group(0) @binding(7)
+var<uniform> shadow_distance: f32;
+// world space offsets applied before shadow lookups. x: towards the light, y: along the surface normal.
+@group(0) @binding(8)
+var<uniform> shadow_bias: vec2f;
+@group(0) @binding(9)
+var<uniform> debug_view: u32;
+
+// must match `DebugView`
+const DEBUG_VIEW_DEPTH:u32 = 5;
+const DEBUG_VIEW_NORMALS:u32 = 6;
+const DEBUG_VIEW_ALBEDO:u32 = 7;
+const DEBUG_VIEW_ROUGHNESS:u32 = 8;
+const DEBUG_VIEW_METALLIC:u32 = 9;
+
+// must match `MAX_SHADOWED_LIGHTS` and `CASCADE_COUNT` in the renderer
+const MAX_SHADOWED_LIGHTS:u32 = 4;
+const CASCADE_COUNT:u32 = 3;
+const SHADOW_MAP_LAYERS:u32 = MAX_SHADOWED_LIGHTS * CASCADE_COUNT;
 
 @group(1) @binding(0)
-var fsampler: sampler;
+var shadow_sampler: sampler_comparison;
 
 @group(1) @binding(1)
 var shadow_maps: texture_depth_2d_array;
 
+// one cube per light, indexed by light.
+@group(1) @binding(2)
+var point_shadow_maps: texture_depth_cube_array;
+
+// screen space ambient occlusion of the opaque geometry, 1 where unoccluded.
+@group(1) @binding(3)
+var ambient_occlusion: texture_2d<f32>;
+
+// lights past `MAX_SHADOWED_LIGHTS` have no shadow maps.
+@group(1) @binding(4)
+var<storage, read> lights: array<Light>;
+// per screen tile, the number of lights reaching into it followed by their indices. see light_culling.wgsl
+@group(1) @binding(5)
+var<storage, read> tile_lights: array<u32>;
+// the HDR image before the transmissive primitives were drawn, blurred down its mips. see transmission.wgsl
+@group(1) @binding(6)
+var scene_behind: texture_2d<f32>;
+@group(1) @binding(7)
+var scene_behind_sampler: sampler;
+// shadow_maps as plain floats, for the overlay.
+@group(1) @binding(8)
+var shadow_map_depths: texture_2d_array<f32>;
+
+// must match light_culling.wgsl
+const TILE_SIZE:u32 = 16;
+const MAX_LIGHTS_PER_TILE:u32 = 63;
+
 @group(2) @binding(0)
 var<uniform> material: Material;
+// world matrices of the primitive's skin, see `Skin::joint_matrices`.
+@group(2) @binding(1)
+var<uniform> joint_matrices: array<mat4x4f, MAX_JOINTS>;
+
+// must match `MAX_JOINTS`
+const MAX_JOINTS:u32 = 128;
+
+// each texture is sampled with its own glTF sampler.
+@group(3) @binding(0)
+var base_color_texture: texture_2d<f32>;
+@group(3) @binding(1)
+var base_color_sampler: sampler;
+@group(3) @binding(2)
+var normal_texture: texture_2d<f32>;
+@group(3) @binding(3)
+var normal_sampler: sampler;
+// glTF packs roughness in green and metallic in blue.
+@group(3) @binding(4)
+var metallic_roughness_texture: texture_2d<f32>;
+@group(3) @binding(5)
+var metallic_roughness_sampler: sampler;
+// baked ambient occlusion in red.
+@group(3) @binding(6)
+var occlusion_texture: texture_2d<f32>;
+@group(3) @binding(7)
+var occlusion_sampler: sampler;
+@group(3) @binding(8)
+var emissive_texture: texture_2d<f32>;
+@group(3) @binding(9)
+var emissive_sampler: sampler;
+@group(3) @binding(10)
+var clearcoat_normal_texture: texture_2d<f32>;
+@group(3) @binding(11)
+var clearcoat_normal_sampler: sampler;
+// KHR_materials_transmission's factor in red.
+@group(3) @binding(12)
+var transmission_texture: texture_2d<f32>;
+@group(3) @binding(13)
+var transmission_sampler: sampler;
 
 @fragment
 fn fs_main(
-    in: VertexOutput
+    in: VertexOutput,
+    @builtin(front_facing) front_facing: bool,
 ) -> @location(0) vec4f {
     var color = vec3f(0.0);
 
-    let normal = in.normal;
+    let uv = material_uv(in.uv);
+    let base_color = material.base_color * in.color * textureSample(base_color_texture, base_color_sampler, uv);
+    let albedo = base_color.xyz;
+    let metallic_roughness = textureSample(metallic_roughness_texture, metallic_roughness_sampler, uv);
+    let metallic = material.metallic * metallic_roughness.b;
+    let roughness = material.roughness * metallic_roughness.g;
+    let baked_occlusion = 1.0 + material.occlusion_strength * (textureSample(occlusion_texture, occlusion_sampler, uv).r - 1.0);
+
+    // only double-sided materials have visible back faces. light them from the viewer's side.
+    let facing = select(-1.0, 1.0, front_facing);
+    let normal = facing * perturb_normal(in, textureSample(normal_texture, normal_sampler, uv).xyz, material.normal_scale);
+    let coat_normal = facing * perturb_normal(in, textureSample(clearcoat_normal_texture, clearcoat_normal_sampler, uv).xyz, material.clearcoat_normal_scale);
+    let emissive = material.emissive * textureSample(emissive_texture, emissive_sampler, uv).rgb;
+    let transmission_factor = material.transmission * textureSample(transmission_texture, transmission_sampler, uv).r;
+    let transmission = select(0.0, transmission_factor, (material.flags & MATERIAL_TRANSMISSION) != 0);
+    // normal offset uses the geometric normal, normal maps don't move the surface.
+    let shadow_normal_offset = facing * normalize(in.normal) * shadow_bias.y;
+
+    if material.alpha_mode == ALPHA_MASK && base_color.a < material.alpha_cutoff {
+        discard;
+    }
     let light_out = camera_pos - in.world_pos;
     let view_dir = normalize(light_out);
+    let view_depth = dot(in.world_pos - camera_pos, camera_dir);
+
+    switch debug_view {
+        case DEBUG_VIEW_DEPTH: {
+            return vec4f(vec3f(saturate(view_depth / shadow_distance)), 1.0);
+        }
+        case DEBUG_VIEW_NORMALS: {
+            return vec4f(0.5 * normal + 0.5, 1.0);
+        }
+        case DEBUG_VIEW_ALBEDO: {
+            return vec4f(albedo, 1.0);
+        }
+        case DEBUG_VIEW_ROUGHNESS: {
+            return vec4f(vec3f(roughness), 1.0);
+        }
+        case DEBUG_VIEW_METALLIC: {
+            return vec4f(vec3f(metallic), 1.0);
+        }
+        default: {}
+    }
+
+    let alpha = select(1.0, base_color.a, material.alpha_mode == ALPHA_BLEND);
+    if (material.flags & MATERIAL_UNLIT) != 0 {
+        return vec4f(albedo, alpha);
+    }
     
-    for(var i = 0; i < 4; i++) {
+    let tile = vec2u(in.position.xy) / TILE_SIZE;
+    let tile_offset = (tile.y * tile_columns + tile.x) * (MAX_LIGHTS_PER_TILE + 1);
+    for(var j = 0u; j < tile_lights[tile_offset]; j++) {
+        let i = tile_lights[tile_offset + 1 + j];
         let light = lights[i];
-        let light_space_pos = into_vec3_pos(light.matrix * vec4f(in.world_pos, 1.0));
-        var shadow = 0.0;
+        let shadowed = i < MAX_SHADOWED_LIGHTS;
 
-        let shadow_map_texel_size = 1.0 / vec2f(textureDimensions(shadow_maps, i));
-        for(var x = -1; x <= 1; x++){
-            for(var y = -1; y <= 1; y++){
-                let tex_coord = ndc_to_uv(light_space_pos.xy) + (vec2f(f32(x), f32(y)) * shadow_map_texel_size);
-                let shadow_depth = textureSample(shadow_maps, fsampler, tex_coord, i);
-
-                if light_space_pos.z - 0.0000003 > shadow_depth {
-                    shadow += 1.0;
-                }
-            }
-        }
-        shadow /= 9.0;
-        
         switch light.typ {
-            case 1: {
-                // Point light
+            case LIGHT_POINT: {
                 let light_in = in.world_pos - light.pos;
                 let light_dir = normalize(-light_in);
 
+                var shadow = 0.0;
+                if shadowed {
+                    let shadow_pos = in.world_pos + shadow_normal_offset + light_dir * shadow_bias.x;
+                    shadow = point_shadow_factor(i, shadow_pos - light.pos);
+                }
                 let light_distance = length(light_in);
-                let light_power = light.intensity / (light_distance * light_distance);
-                color += brdf(light_dir, view_dir, normal) * light_power * max(dot(normal, light_dir), 0.0);
+                let light_power = (1.0 - shadow) * light.intensity / (light_distance * light_distance);
+                color += shade(light_dir, view_dir, normal, coat_normal, albedo, metallic, roughness, transmission) * light_power;
             }
-            case 2: {
-                // Directional light
+            case LIGHT_DIRECTIONAL: {
                 let light_in = light.direction;
                 let light_dir = normalize(-light_in);
 
-                let light_power = 0.2 * light.intensity;
-                color += brdf(light_dir, view_dir, normal) * light_power * max(dot(normal, light_dir), 0.0);
+                var shadow = 0.0;
+                if shadowed && view_depth < cascade_splits.z {
+                    let cascade = select(0u, 1u, view_depth >= cascade_splits.x) + select(0u, 1u, view_depth >= cascade_splits.y);
+                    let layer = i * CASCADE_COUNT + cascade;
+                    let shadow_pos = in.world_pos + shadow_normal_offset + light_dir * shadow_bias.x;
+                    shadow = shadow_factor(cascade_matrices[layer], layer, shadow_pos);
+                }
+
+                let light_power = (1.0 - shadow) * 0.2 * light.intensity;
+                color += shade(light_dir, view_dir, normal, coat_normal, albedo, metallic, roughness, transmission) * light_power;
             }
-            case 3: {
-                // Spot light
+            case LIGHT_SPOT: {
                 let light_in = in.world_pos - light.pos;
                 let light_dir = normalize(-light_in);
 
-                let angle = acos(dot(light.direction,-light_dir));
+                // cone angles are half-angles from the spot axis
+                let angle = acos(clamp(dot(light.direction, -light_dir), -1.0, 1.0));
                 var falloff = 0.0;
                 if angle < light.extra1 {
                     falloff = 1.0;
@@ -139,9 +329,14 @@ fn fs_main(
                     break;
                 }
 
+                var shadow = 0.0;
+                if shadowed {
+                    let shadow_pos = in.world_pos + shadow_normal_offset + light_dir * shadow_bias.x;
+                    shadow = shadow_factor(light.matrix, i * CASCADE_COUNT, shadow_pos);
+                }
                 let light_distance = length(light_in);
                 let light_power = (1.0 - shadow) * 0.2 * light.intensity * falloff / (light_distance * light_distance);
-                color += brdf(light_dir, view_dir, normal) * light_power * max(dot(normal, light_dir), 0.0);
+                color += shade(light_dir, view_dir, normal, coat_normal, albedo, metallic, roughness, transmission) * light_power;
             }
             default: {
                 
@@ -149,71 +344,266 @@ fn fs_main(
         }
     }
 
-    // ambient
-    color += 0.1 * material.base_color.xyz;
+    // ambient, diffused by dielectrics and reflected by metals.
+    // transparent primitives aren't in the occlusion texture, it holds what's behind them.
+    let occlusion = select(textureLoad(ambient_occlusion, vec2u(in.position.xy), 0).r, 1.0, material.alpha_mode == ALPHA_BLEND);
+    let ambient = albedo * (1.0 - metallic) * (1.0 - transmission) + specular_color(albedo, metallic);
+    color += 0.1 * ambient * occlusion * baked_occlusion;
+    color += emissive;
+
+    // KHR_materials_transmission: what the base doesn't diffuse comes through from behind, tinted by
+    // the base color and blurrier the rougher it is. thin walled, the view ray isn't refracted.
+    if transmission > 0.0 {
+        let screen_uv = in.position.xy / vec2f(textureDimensions(scene_behind));
+        let lod = roughness * f32(textureNumLevels(scene_behind) - 1);
+        let behind = textureSampleLevel(scene_behind, scene_behind_sampler, screen_uv, lod).rgb;
+        let f_0 = specular_color(albedo, metallic);
+        let fresnel = f_0 + (1.0 - f_0) * pow(1.0 - saturate(dot(normal, view_dir)), 5.0);
+        color += (vec3f(1.0) - fresnel) * (1.0 - metallic) * transmission * albedo * behind;
+    }
+
+    // tone mapping happens in a later pass, this writes linear HDR color.
+    return vec4f(color, alpha);
+}
+
+struct PrepassOutput {
+    @location(0) position: vec4f,
+    @location(1) normal: vec4f,
+}
+
+// depth only, for the depth prepass. masked texels are discarded like in `fs_main`.
+@fragment
+fn fs_depth(in: VertexOutput) -> @location(0) vec4f {
+    let base_color = material.base_color * in.color * textureSample(base_color_texture, base_color_sampler, material_uv(in.uv));
+    if material.alpha_mode == ALPHA_MASK && base_color.a < material.alpha_cutoff {
+        discard;
+    }
+    return vec4f(0.0);
+}
+
+// world space G-buffer for the ambient occlusion pass.
+@fragment
+fn fs_prepass(
+    in: VertexOutput,
+    @builtin(front_facing) front_facing: bool,
+) -> PrepassOutput {
+    let base_color = material.base_color * in.color * textureSample(base_color_texture, base_color_sampler, material_uv(in.uv));
+    if material.alpha_mode == ALPHA_MASK && base_color.a < material.alpha_cutoff {
+        discard;
+    }
+
+    var out: PrepassOutput;
+    out.position = vec4f(in.world_pos - camera_pos, 1.0);
+    out.normal = vec4f(select(-1.0, 1.0, front_facing) * normalize(in.normal), 0.0);
+    return out;
+}
+
+// fraction of the kernel around `world_pos` that is occluded in the given shadow map layer.
+fn shadow_factor(light_matrix: mat4x4f, layer: u32, world_pos: vec3f) -> f32 {
+    let light_space_pos = into_vec3_pos(light_matrix * vec4f(world_pos, 1.0));
+    var shadow = 0.0;
+
+    // percentage-closer filtering: average depth comparisons around the projected texel.
+    let radius = i32(pcf_radius);
+    let shadow_map_texel_size = 1.0 / vec2f(textureDimensions(shadow_maps));
+    for(var x = -radius; x <= radius; x++){
+        for(var y = -radius; y <= radius; y++){
+            let tex_coord = ndc_to_uv(light_space_pos.xy) + (vec2f(f32(x), f32(y)) * shadow_map_texel_size);
+            shadow += 1.0 - textureSampleCompareLevel(shadow_maps, shadow_sampler, tex_coord, layer, light_space_pos.z);
+        }
+    }
+    let kernel_width = f32(2 * radius + 1);
+    return shadow / (kernel_width * kernel_width);
+}
+
+// `light_in` points from the light to the shaded point.
+fn point_shadow_factor(light_index: u32, light_in: vec3f) -> f32 {
+    let depth = length(light_in) / shadow_distance;
+    if depth >= 1.0 {
+        return 0.0;
+    }
+    return 1.0 - textureSampleCompareLevel(point_shadow_maps, shadow_sampler, light_in, light_index, depth);
+}
+
+// texture coordinates of the material's textures at a vertex UV.
+fn material_uv(uv: vec2f) -> vec2f {
+    return (material.uv_transform * vec3f(uv, 1.0)).xy;
+}
 
-    color = tone_map(color);
-    return vec4f(color, 1.0);
+// applies a sampled normal map texel. vertices without a tangent keep the geometric normal.
+fn perturb_normal(in: VertexOutput, texel: vec3f, scale: f32) -> vec3f {
+    let normal = normalize(in.normal);
+    let tangent_normal = texel * 2.0 - 1.0;
+    if dot(in.tangent.xyz, in.tangent.xyz) == 0.0 {
+        return normal;
+    }
+    let tangent = normalize(in.tangent.xyz - normal * dot(normal, in.tangent.xyz));
+    let bitangent = cross(normal, tangent) * in.tangent.w;
+    let scaled = tangent_normal * vec3f(scale, scale, 1.0);
+    return normalize(mat3x3f(tangent, bitangent, normal) * scaled);
 }
 
 @vertex
 fn vs_light(
     in: VertexInput,
 ) -> @builtin(position) vec4f {
-    let model = mat4x4f(in.model_0, in.model_1, in.model_2, in.model_3);
+    let model = model_matrix(in);
     let world_pos = model * vec4f(in.position, 1.0);
     return camera * world_pos;
 }
 
+fn is_skinned(in: VertexInput) -> bool {
+    return any(in.weights != vec4f(0.0));
+}
+
+// linear blend skinning. joint matrices are already in world space, so skinned vertices ignore the instance's model matrix.
+fn model_matrix(in: VertexInput) -> mat4x4f {
+    if !is_skinned(in) {
+        return mat4x4f(in.model_0, in.model_1, in.model_2, in.model_3);
+    }
+    return in.weights.x * joint_matrices[in.joints.x]
+        + in.weights.y * joint_matrices[in.joints.y]
+        + in.weights.z * joint_matrices[in.joints.z]
+        + in.weights.w * joint_matrices[in.joints.w];
+}
+
 @fragment
 fn fs_light() -> @location(0) vec4f {
     return vec4f(0.0, 0.0, 0.0, 1.0);
 }
 
-fn brdf(light_dir: vec3f, view_dir: vec3f, normal: vec3f) -> vec3f {
+// how far the outline of the highlighted mesh reaches past it, in radians seen from the camera.
+const OUTLINE_WIDTH: f32 = 0.004;
+const OUTLINE_COLOR: vec3f = vec3f(1.0, 0.5, 0.0);
+
+// the highlighted mesh grown along its normals. the mask of the mesh itself leaves the rim around it.
+@vertex
+fn vs_outline(
+    in: VertexInput,
+) -> @builtin(position) vec4f {
+    let model = model_matrix(in);
+    var rot = mat3x3f(in.rot_0, in.rot_1, in.rot_2);
+    if is_skinned(in) {
+        rot = mat3x3f(model[0].xyz, model[1].xyz, model[2].xyz);
+    }
+    let world_pos = (model * vec4f(in.position, 1.0)).xyz;
+    // grown by the same angle at any distance, so the outline keeps its width on screen.
+    let grown = world_pos + normalize(rot * in.normal) * OUTLINE_WIDTH * distance(world_pos, camera_pos);
+    return camera * vec4f(grown, 1.0);
+}
+
+@fragment
+fn fs_outline() -> @location(0) vec4f {
+    return vec4f(OUTLINE_COLOR, 1.0);
+}
+
+struct PointLightOutput {
+    @builtin(position) position: vec4f,
+    @location(0) world_pos: vec3f,
+}
+
+// renders a point light cube map face. camera_pos holds the light position.
+@vertex
+fn vs_point_light(
+    in: VertexInput,
+) -> PointLightOutput {
+    var out: PointLightOutput;
+    let model = model_matrix(in);
+    let world_pos = model * vec4f(in.position, 1.0);
+    out.position = camera * world_pos;
+    out.world_pos = world_pos.xyz;
+    return out;
+}
+
+@fragment
+fn fs_point_light(in: PointLightOutput) -> @builtin(frag_depth) f32 {
+    return length(in.world_pos - camera_pos) / shadow_distance;
+}
+
+struct FullOutput {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+    @location(1) @interpolate(flat) layer: u32,
+}
+
+// full-screen triangle. the instance index selects which shadow map layer to show.
+@vertex
+fn vs_full(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> FullOutput {
+    var out: FullOutput;
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    out.position = vec4f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    out.layer = instance_index;
+    return out;
+}
+
+@fragment
+fn fs_full(
+    in: FullOutput
+) -> @location(0) vec4f {
+    let size = textureDimensions(shadow_map_depths);
+    let coord = vec2u(clamp(in.uv, vec2f(0.0), vec2f(1.0)) * vec2f(size - 1));
+    let depth = textureLoad(shadow_map_depths, coord, in.layer, 0).r;
+    return vec4f(vec3f(depth), 1.0);
+}
+
+// reflectance at normal incidence. dielectrics reflect 4%, metals their albedo.
+fn specular_color(albedo: vec3f, metallic: f32) -> vec3f {
+    return mix(vec3f(0.04), albedo, metallic);
+}
+
+// Cook-Torrance: lambertian diffuse plus the GGX specular lobe. light the fresnel term reflects
+// isn't diffused, and metals don't diffuse at all. neither does the transmitted part of the base.
+fn brdf(light_dir: vec3f, view_dir: vec3f, normal: vec3f, albedo: vec3f, metallic: f32, roughness: f32, transmission: f32) -> vec3f {
     let halfway = normalize(light_dir + view_dir);
 
-    let roughness = material.roughness;
-    let metallic = material.metallic;
-    var albedo = material.base_color.xyz;
-    
-    let f_0 = mix(vec3f(0.4), albedo, metallic);
-    let fresnel = f_0 + (1 - f_0) * pow((1 - dot(halfway, view_dir)), 5.0);
+    let f_0 = specular_color(albedo, metallic);
+    let fresnel = f_0 + (1.0 - f_0) * pow(1.0 - saturate(dot(halfway, view_dir)), 5.0);
 
-    let k_s = fresnel;
-    let k_d = (vec3f(1.0) - k_s) * (1.0 - metallic);
+    let k_d = (vec3f(1.0) - fresnel) * (1.0 - metallic) * (1.0 - transmission);
     let diffuse = albedo / PI;
 
-    let roughness2 = roughness * roughness;
-    let distribution = roughness2 / (PI * pow(pow(dot(normal, halfway), 2.0) * (roughness2 - 1) + 1, 2.0));
-
-    let k = pow(roughness2 + 1.0, 2.0) / 8.0;
-
-    let normal_dot_light = dot(normal, light_dir);
-    let normal_dot_view = dot(normal, view_dir);
-    let geometry = (normal_dot_light / (normal_dot_light * (1-k)+k))
-        * (normal_dot_view / (normal_dot_view * (1-k)+k));
-
-    let specular = (fresnel * distribution * geometry)
-        / (4 * dot(light_dir, halfway) * dot(view_dir, halfway));
-    return k_d * diffuse + k_s * specular;
-}
-
-fn tone_map(hdr: vec3f) -> vec3f {
-    let m1 = mat3x3(
-        0.59719, 0.07600, 0.02840,
-        0.35458, 0.90834, 0.13383,
-        0.04823, 0.01566, 0.83777,
-    );
-    let m2 = mat3x3(
-        1.60475, -0.10208, -0.00327,
-        -0.53108,  1.10813, -0.07276,
-        -0.07367, -0.00605,  1.07602,
-    );
-    let v = m1 * hdr;
-    let a = v * (v + 0.0245786) - 0.000090537;
-    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
-    return clamp(m2 * (a / b), vec3(0.0), vec3(1.0));
+    let specular = fresnel * specular_lobe(light_dir, view_dir, normal, roughness);
+    return k_d * diffuse + specular;
+}
+
+// the specular BRDF without its fresnel term: GGX distribution and Smith-Schlick geometry,
+// over 4 (n.l) (n.v). `roughness` is perceptual, squared into the GGX alpha.
+fn specular_lobe(light_dir: vec3f, view_dir: vec3f, normal: vec3f, roughness: f32) -> f32 {
+    let halfway = normalize(light_dir + view_dir);
+    let normal_dot_halfway = saturate(dot(normal, halfway));
+    // a zero alpha would make the highlight infinitely small and bright.
+    let alpha = max(roughness * roughness, 0.002);
+    let alpha2 = alpha * alpha;
+    let d = normal_dot_halfway * normal_dot_halfway * (alpha2 - 1.0) + 1.0;
+    let distribution = alpha2 / (PI * d * d);
+
+    // remapped for analytic lights, see Karis 2013.
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    let normal_dot_light = max(dot(normal, light_dir), 1e-4);
+    let normal_dot_view = max(dot(normal, view_dir), 1e-4);
+    let geometry = (normal_dot_light / (normal_dot_light * (1.0 - k) + k))
+        * (normal_dot_view / (normal_dot_view * (1.0 - k) + k));
+
+    return distribution * geometry / (4.0 * normal_dot_light * normal_dot_view);
+}
+
+// reflected radiance per unit of incoming light. a clearcoat is a dielectric layer over the base
+// material, whatever its fresnel reflects doesn't reach the base.
+fn shade(light_dir: vec3f, view_dir: vec3f, normal: vec3f, coat_normal: vec3f, albedo: vec3f, metallic: f32, roughness: f32, transmission: f32) -> vec3f {
+    let base = brdf(light_dir, view_dir, normal, albedo, metallic, roughness, transmission) * max(dot(normal, light_dir), 0.0);
+    if (material.flags & MATERIAL_CLEARCOAT) == 0 {
+        return base;
+    }
+    let halfway = normalize(light_dir + view_dir);
+    let coat_fresnel = material.clearcoat * (0.04 + 0.96 * pow(1.0 - saturate(dot(halfway, view_dir)), 5.0));
+    // a perfectly smooth coat has no highlight at all outside of the mirror direction.
+    let coat_roughness = max(material.clearcoat_roughness, 0.05);
+    let coat = specular_lobe(light_dir, view_dir, coat_normal, coat_roughness) * max(dot(coat_normal, light_dir), 0.0);
+    return base * (1.0 - coat_fresnel) + coat_fresnel * coat;
 }
 
 fn into_vec3_pos(pos: vec4f) -> vec3f {
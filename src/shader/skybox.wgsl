@@ -0,0 +1,57 @@
+@group(0) @binding(0)
+var<uniform> inverse_camera_matrix: mat4x4f;
+// `SkyGradient`, used without an environment.
+@group(0) @binding(1)
+var<uniform> gradient_bottom: vec3f;
+@group(0) @binding(2)
+var<uniform> gradient_top: vec3f;
+
+@group(1) @binding(0)
+var environment: texture_cube<f32>;
+@group(1) @binding(1)
+var environment_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4f,
+    @location(0) ndc: vec2f,
+}
+
+// full-screen triangle, same as `vs_full` in shader.wgsl but on the far plane.
+@vertex
+fn vs_sky(
+    @builtin(vertex_index) vertex_index: u32,
+) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    let ndc = vec2f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0));
+    var out: VertexOutput;
+    // reverse-Z, the far plane is at depth 0.
+    out.position = vec4f(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_sky(in: VertexOutput) -> @location(0) vec4f {
+    let color = textureSampleLevel(environment, environment_sampler, view_ray(in.ndc), 0.0);
+    return vec4f(color.rgb, 1.0);
+}
+
+// blends from the bottom color looking straight down to the top color looking straight up.
+@fragment
+fn fs_gradient(in: VertexOutput) -> @location(0) vec4f {
+    let up = normalize(view_ray(in.ndc)).y;
+    return vec4f(mix(gradient_bottom, gradient_top, fma(up, 0.5, 0.5)), 1.0);
+}
+
+// world space direction of the camera ray through `ndc`.
+fn view_ray(ndc: vec2f) -> vec3f {
+    // the far plane may be at infinity, so the ray goes through two nearer points instead.
+    let near = unproject(vec3f(ndc, 1.0));
+    let far = unproject(vec3f(ndc, 0.5));
+    return far - near;
+}
+
+fn unproject(ndc: vec3f) -> vec3f {
+    let position = inverse_camera_matrix * vec4f(ndc, 1.0);
+    return position.xyz / position.w;
+}
@@ -0,0 +1,108 @@
+@group(0) @binding(0)
+var<uniform> camera: mat4x4f;
+@group(0) @binding(1)
+var<uniform> camera_pos: vec3f;
+@group(0) @binding(2)
+var<uniform> camera_dir: vec3f;
+@group(0) @binding(3)
+var<uniform> radius: f32;
+@group(0) @binding(4)
+var<uniform> strength: f32;
+
+// written by the prepass. positions are relative to the camera, w is 0 where nothing was drawn.
+@group(1) @binding(0)
+var position_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var normal_texture: texture_2d<f32>;
+
+// raw occlusion, read by the blur pass. its own group so the ssao pass can render into it.
+@group(2) @binding(0)
+var occlusion_texture: texture_2d<f32>;
+
+const SAMPLE_COUNT:u32 = 16;
+const GOLDEN_ANGLE:f32 = 2.39996323;
+
+// full-screen triangle, same as `vs_full` in shader.wgsl.
+@vertex
+fn vs_full(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4f {
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    return vec4f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0), 0.0, 1.0);
+}
+
+// fraction of a normal oriented hemisphere around each pixel that is hidden behind other geometry.
+@fragment
+fn fs_ssao(
+    @builtin(position) position: vec4f,
+) -> @location(0) vec4f {
+    let pixel = vec2u(position.xy);
+    let view_pos = textureLoad(position_texture, pixel, 0);
+    if view_pos.w == 0.0 {
+        return vec4f(1.0);
+    }
+    let world_pos = view_pos.xyz + camera_pos;
+    let normal = normalize(textureLoad(normal_texture, pixel, 0).xyz);
+
+    // orthonormal basis around the normal, spun per pixel so the kernel's banding turns into noise the blur removes.
+    let helper = select(vec3f(1.0, 0.0, 0.0), vec3f(0.0, 1.0, 0.0), abs(normal.x) > 0.9);
+    let tangent = normalize(cross(helper, normal));
+    let bitangent = cross(normal, tangent);
+    let spin = 6.2831853 * hash(position.xy);
+
+    let size = vec2f(textureDimensions(position_texture));
+    var occlusion = 0.0;
+    for(var i = 0u; i < SAMPLE_COUNT; i++) {
+        // spiral over the hemisphere, denser close to the center.
+        let t = (f32(i) + 0.5) / f32(SAMPLE_COUNT);
+        let angle = f32(i) * GOLDEN_ANGLE + spin;
+        let z = sqrt(1.0 - t);
+        let r = sqrt(t);
+        let scale = mix(0.1, 1.0, t * t);
+        let offset = (tangent * (r * cos(angle)) + bitangent * (r * sin(angle)) + normal * z) * scale * radius;
+        let sample_pos = world_pos + offset;
+
+        let clip = camera * vec4f(sample_pos, 1.0);
+        let uv = ndc_to_uv(clip.xy / clip.w);
+        if any(uv < vec2f(0.0)) || any(uv >= vec2f(1.0)) {
+            continue;
+        }
+        let scene_pos = textureLoad(position_texture, vec2u(uv * size), 0);
+        if scene_pos.w == 0.0 {
+            continue;
+        }
+
+        let sample_depth = dot(sample_pos - camera_pos, camera_dir);
+        let scene_depth = dot(scene_pos.xyz, camera_dir);
+        // geometry far in front of the sample belongs to another object and shouldn't darken this one.
+        let range = smoothstep(0.0, 1.0, radius / max(length(view_pos.xyz - scene_pos.xyz), 0.0001));
+        occlusion += select(0.0, range, scene_depth < sample_depth - 0.02);
+    }
+
+    return vec4f(vec3f(clamp(1.0 - strength * occlusion / f32(SAMPLE_COUNT), 0.0, 1.0)), 1.0);
+}
+
+// 4x4 box blur over the raw occlusion.
+@fragment
+fn fs_blur(
+    @builtin(position) position: vec4f,
+) -> @location(0) vec4f {
+    let size = vec2i(textureDimensions(occlusion_texture));
+    let pixel = vec2i(position.xy);
+    var occlusion = 0.0;
+    for(var x = -2; x < 2; x++) {
+        for(var y = -2; y < 2; y++) {
+            let coord = clamp(pixel + vec2i(x, y), vec2i(0), size - 1);
+            occlusion += textureLoad(occlusion_texture, coord, 0).r;
+        }
+    }
+    return vec4f(vec3f(occlusion / 16.0), 1.0);
+}
+
+fn hash(p: vec2f) -> f32 {
+    return fract(sin(dot(p, vec2f(12.9898, 78.233))) * 43758.5453);
+}
+
+fn ndc_to_uv(coord: vec2f) -> vec2f {
+    return vec2f(fma(coord.x, 0.5, 0.5), fma(coord.y, -0.5, 0.5));
+}
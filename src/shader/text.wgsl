@@ -0,0 +1,51 @@
+@group(0) @binding(0)
+var<uniform> screen_size: vec2f;
+// glyph indices, four to an element.
+@group(0) @binding(1)
+var<uniform> text: array<vec4u, 8>;
+
+// glyphs side by side, white where set.
+@group(1) @binding(0)
+var font_atlas: texture_2d<f32>;
+
+const GLYPH_SIZE = vec2i(5, 7);
+// a glyph with a pixel of background around it.
+const CELL_SIZE = vec2f(7.0, 9.0);
+// screen pixels per font pixel.
+const SCALE: f32 = 2.0;
+const ORIGIN = vec2f(8.0, 8.0);
+
+struct VertexOutput {
+    @builtin(position) position: vec4f,
+    // position in the character's cell, in font pixels.
+    @location(0) cell: vec2f,
+    @location(1) @interpolate(flat) glyph: u32,
+}
+
+// one quad per character, drawn as a 4 vertex triangle strip.
+@vertex
+fn vs_text(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+    let cell = vec2f(f32(vertex_index & 1), f32(vertex_index >> 1)) * CELL_SIZE;
+    let pixel = ORIGIN + (vec2f(f32(instance_index) * CELL_SIZE.x, 0.0) + cell) * SCALE;
+
+    var out: VertexOutput;
+    out.position = vec4f(fma(pixel / screen_size, vec2f(2.0, -2.0), vec2f(-1.0, 1.0)), 0.0, 1.0);
+    out.cell = cell;
+    out.glyph = text[instance_index / 4][instance_index % 4];
+    return out;
+}
+
+@fragment
+fn fs_text(in: VertexOutput) -> @location(0) vec4f {
+    let texel = vec2i(floor(in.cell)) - vec2i(1, 1);
+    if all(texel >= vec2i(0)) && all(texel < GLYPH_SIZE) {
+        let atlas_texel = vec2i(i32(in.glyph) * GLYPH_SIZE.x, 0) + texel;
+        if textureLoad(font_atlas, atlas_texel, 0).r > 0.5 {
+            return vec4f(1.0);
+        }
+    }
+    return vec4f(0.0, 0.0, 0.0, 0.6);
+}
@@ -0,0 +1,68 @@
+// must match `ToneMapping`
+const TONE_MAPPING_REINHARD:u32 = 0;
+const TONE_MAPPING_ACES:u32 = 1;
+// must match `TONE_MAPPING_NONE` in the renderer
+const TONE_MAPPING_NONE:u32 = 2;
+
+@group(0) @binding(0)
+var<uniform> exposure: f32;
+@group(0) @binding(1)
+var<uniform> tone_mapping: u32;
+@group(0) @binding(2)
+var<uniform> bloom_intensity: f32;
+
+@group(1) @binding(0)
+var hdr_texture: texture_2d<f32>;
+// half resolution, see `Bloom`.
+@group(1) @binding(1)
+var bloom_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var bloom_sampler: sampler;
+
+// full-screen triangle, same as `vs_full` in shader.wgsl.
+@vertex
+fn vs_full(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4f {
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    return vec4f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0), 0.0, 1.0);
+}
+
+@fragment
+fn fs_tone_map(
+    @builtin(position) position: vec4f,
+) -> @location(0) vec4f {
+    let hdr = textureLoad(hdr_texture, vec2u(position.xy), 0);
+    let uv = position.xy / vec2f(textureDimensions(hdr_texture));
+    let bloom = textureSampleLevel(bloom_texture, bloom_sampler, uv, 0.0).rgb;
+    let color = exposure * (hdr.rgb + bloom_intensity * bloom);
+    switch tone_mapping {
+        case TONE_MAPPING_REINHARD: {
+            return vec4f(color / (1.0 + color), hdr.a);
+        }
+        case TONE_MAPPING_NONE: {
+            return vec4f(color, hdr.a);
+        }
+        default: {
+            return vec4f(aces(color), hdr.a);
+        }
+    }
+}
+
+// fitted ACES filmic curve.
+fn aces(hdr: vec3f) -> vec3f {
+    let m1 = mat3x3(
+        0.59719, 0.07600, 0.02840,
+        0.35458, 0.90834, 0.13383,
+        0.04823, 0.01566, 0.83777,
+    );
+    let m2 = mat3x3(
+        1.60475, -0.10208, -0.00327,
+        -0.53108,  1.10813, -0.07276,
+        -0.07367, -0.00605,  1.07602,
+    );
+    let v = m1 * hdr;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return clamp(m2 * (a / b), vec3(0.0), vec3(1.0));
+}
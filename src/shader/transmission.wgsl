@@ -0,0 +1,42 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct FullOutput {
+    @builtin(position) position: vec4f,
+    @location(0) uv: vec2f,
+}
+
+// full-screen triangle, same as `vs_full` in shader.wgsl.
+@vertex
+fn vs_full(
+    @builtin(vertex_index) vertex_index: u32,
+) -> FullOutput {
+    var out: FullOutput;
+    let uv = vec2f(f32((vertex_index << 1) & 2), f32(vertex_index & 2));
+    out.position = vec4f(fma(uv.x, 2.0, -1.0), fma(uv.y, -2.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// the HDR image as is, for smooth surfaces.
+@fragment
+fn fs_transmission_copy(in: FullOutput) -> @location(0) vec4f {
+    return vec4f(textureSampleLevel(source, source_sampler, in.uv, 0.0).rgb, 1.0);
+}
+
+// 3x3 binomial kernel over the previous mip's texels, as in bloom.wgsl.
+@fragment
+fn fs_transmission_downsample(in: FullOutput) -> @location(0) vec4f {
+    let texel_size = 1.0 / vec2f(textureDimensions(source));
+    var color = vec3f(0.0);
+    for(var x = -1; x <= 1; x++) {
+        for(var y = -1; y <= 1; y++) {
+            let weight = f32((2 - abs(x)) * (2 - abs(y))) / 16.0;
+            let offset = vec2f(f32(x), f32(y)) * texel_size;
+            color += weight * textureSampleLevel(source, source_sampler, in.uv + offset, 0.0).rgb;
+        }
+    }
+    return vec4f(color, 1.0);
+}
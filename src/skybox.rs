@@ -0,0 +1,193 @@
+use crate::renderer::{HDR_FORMAT, SkyGradient, UniformGroup};
+use glam::{Mat4, Vec3};
+
+// draws the environment cube map behind the scene, in the main pass after the opaque primitives.
+// without an environment it draws a `SkyGradient` if there's one, otherwise nothing is drawn
+// and the background stays the clear color.
+pub(crate) struct Skybox {
+    uniform: UniformGroup,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    pipeline: wgpu::RenderPipeline,
+    gradient_pipeline: wgpu::RenderPipeline,
+
+    environment: Option<(crate::texture::Texture, wgpu::BindGroup)>,
+}
+
+impl Skybox {
+    // `sample_count` must match the main pass.
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
+        let mut uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<Mat4>() as u64,
+                size_of::<Vec3>() as u64,
+                size_of::<Vec3>() as u64,
+            ],
+        );
+        uniform.add_bind_group(device);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Skybox bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("skybox sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/skybox.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&uniform.bind_group_layout, &bind_group_layout],
+            immediate_size: 0,
+        });
+        let create_pipeline = |label, layout: &wgpu::PipelineLayout, fs_entry_point| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_sky"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(fs_entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                // the triangle lies on the far plane, so it only covers pixels no geometry was drawn to.
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: wgpu::TextureFormat::Depth32Float,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::GreaterEqual,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        let pipeline = create_pipeline("Skybox", &pipeline_layout, "fs_sky");
+        // only reads the uniform.
+        let gradient_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&uniform.bind_group_layout],
+                immediate_size: 0,
+            });
+        let gradient_pipeline =
+            create_pipeline("Sky gradient", &gradient_pipeline_layout, "fs_gradient");
+
+        Self {
+            uniform,
+            bind_group_layout,
+            sampler,
+            pipeline,
+            gradient_pipeline,
+            environment: None,
+        }
+    }
+
+    // `faces` in the order +X, -X, +Y, -Y, +Z, -Z, all square and of the same size.
+    // they're sampled with the world space direction, like in OpenGL.
+    // `None` removes the environment.
+    pub fn set_environment(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: Option<&[crate::model::Image; 6]>,
+    ) {
+        self.environment = faces.map(|faces| {
+            let texture = crate::texture::Texture::cube_from_rgba8(
+                device,
+                queue,
+                faces[0].width,
+                faces.each_ref().map(|face| face.pixels.as_slice()),
+                Some("environment"),
+            );
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Skybox bind group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            (texture, bind_group)
+        });
+    }
+
+    // call before the pass `draw` is recorded into.
+    pub fn write(&self, queue: &wgpu::Queue, camera_matrix: Mat4, gradient: Option<&SkyGradient>) {
+        let (bottom, top) = gradient.map_or((Vec3::ZERO, Vec3::ZERO), |gradient| {
+            (gradient.bottom, gradient.top)
+        });
+        self.uniform.write(
+            queue,
+            0,
+            &[
+                bytemuck::cast_slice(&[camera_matrix.inverse()]),
+                bytemuck::cast_slice(&[bottom]),
+                bytemuck::cast_slice(&[top]),
+            ],
+        );
+    }
+
+    // draws the environment, or else the gradient if `gradient`. returns whether it drew anything,
+    // which replaces the bind groups at 0 and 1, so rebind them before drawing anything else.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, gradient: bool) -> bool {
+        match &self.environment {
+            Some((_, bind_group)) => {
+                render_pass.set_pipeline(&self.pipeline);
+                render_pass.set_bind_group(1, bind_group, &[]);
+            }
+            None if gradient => render_pass.set_pipeline(&self.gradient_pipeline),
+            None => return false,
+        }
+        self.uniform.set(render_pass, 0, 0);
+        render_pass.draw(0..3, 0..1);
+        true
+    }
+}
@@ -0,0 +1,278 @@
+use crate::renderer::UniformGroup;
+use glam::{Mat4, Vec3};
+
+// targets of the prepass, which the ssao pass reads.
+// positions are stored relative to the camera, so half floats are precise where it matters.
+pub(crate) const POSITION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+pub(crate) const NORMAL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const OCCLUSION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+// screen space ambient occlusion from a world space position and normal G-buffer.
+// the renderer fills the G-buffer in a prepass, then `render` turns it into a blurred occlusion texture.
+pub(crate) struct Ssao {
+    uniform: UniformGroup,
+    gbuffer_bind_group_layout: wgpu::BindGroupLayout,
+    occlusion_bind_group_layout: wgpu::BindGroupLayout,
+
+    ssao_pipeline: wgpu::RenderPipeline,
+    blur_pipeline: wgpu::RenderPipeline,
+
+    position: crate::texture::Texture,
+    normal: crate::texture::Texture,
+    // the prepass is never multisampled, so it has its own depth buffer.
+    depth: crate::texture::Texture,
+    raw_occlusion: crate::texture::Texture,
+    occlusion: crate::texture::Texture,
+
+    gbuffer_bind_group: wgpu::BindGroup,
+    raw_occlusion_bind_group: wgpu::BindGroup,
+}
+
+impl Ssao {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let mut uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<Mat4>() as u64,
+                size_of::<Vec3>() as u64,
+                size_of::<Vec3>() as u64,
+                size_of::<f32>() as u64,
+                size_of::<f32>() as u64,
+            ],
+        );
+        uniform.add_bind_group(device);
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let gbuffer_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("G-buffer bind group layout"),
+                entries: &[texture_entry(0), texture_entry(1)],
+            });
+        let occlusion_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Occlusion bind group layout"),
+                entries: &[texture_entry(0)],
+            });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/ssao.wgsl"));
+        let create_pipeline =
+            |label, entry_point, bind_group_layouts: &[&wgpu::BindGroupLayout]| {
+                let pipeline_layout =
+                    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: None,
+                        bind_group_layouts,
+                        immediate_size: 0,
+                    });
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_full"),
+                        compilation_options: Default::default(),
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some(entry_point),
+                        compilation_options: Default::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: OCCLUSION_FORMAT,
+                            blend: Some(wgpu::BlendState::REPLACE),
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview_mask: None,
+                    cache: None,
+                })
+            };
+        let ssao_pipeline = create_pipeline(
+            "SSAO",
+            "fs_ssao",
+            &[&uniform.bind_group_layout, &gbuffer_bind_group_layout],
+        );
+        let blur_pipeline = create_pipeline(
+            "SSAO blur",
+            "fs_blur",
+            &[
+                &uniform.bind_group_layout,
+                &gbuffer_bind_group_layout,
+                &occlusion_bind_group_layout,
+            ],
+        );
+
+        let textures = Self::create_textures(device, width, height);
+        let (gbuffer_bind_group, raw_occlusion_bind_group) = Self::create_bind_groups(
+            device,
+            &gbuffer_bind_group_layout,
+            &occlusion_bind_group_layout,
+            &textures,
+        );
+        let [position, normal, depth, raw_occlusion, occlusion] = textures;
+
+        Self {
+            uniform,
+            gbuffer_bind_group_layout,
+            occlusion_bind_group_layout,
+            ssao_pipeline,
+            blur_pipeline,
+            position,
+            normal,
+            depth,
+            raw_occlusion,
+            occlusion,
+            gbuffer_bind_group,
+            raw_occlusion_bind_group,
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let textures = Self::create_textures(device, width, height);
+        (self.gbuffer_bind_group, self.raw_occlusion_bind_group) = Self::create_bind_groups(
+            device,
+            &self.gbuffer_bind_group_layout,
+            &self.occlusion_bind_group_layout,
+            &textures,
+        );
+        [
+            self.position,
+            self.normal,
+            self.depth,
+            self.raw_occlusion,
+            self.occlusion,
+        ] = textures;
+    }
+
+    // in order: position, normal, depth, raw occlusion, occlusion.
+    fn create_textures(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> [crate::texture::Texture; 5] {
+        let target = |format, label| {
+            crate::texture::Texture::create_render_target(
+                device,
+                width,
+                height,
+                format,
+                1,
+                Some(label),
+            )
+        };
+        [
+            target(POSITION_FORMAT, "G-buffer position"),
+            target(NORMAL_FORMAT, "G-buffer normal"),
+            crate::texture::Texture::create_depth_texture(device, width, height, 1, 1),
+            target(OCCLUSION_FORMAT, "raw occlusion"),
+            target(OCCLUSION_FORMAT, "occlusion"),
+        ]
+    }
+
+    fn create_bind_groups(
+        device: &wgpu::Device,
+        gbuffer_layout: &wgpu::BindGroupLayout,
+        occlusion_layout: &wgpu::BindGroupLayout,
+        [position, normal, _, raw_occlusion, _]: &[crate::texture::Texture; 5],
+    ) -> (wgpu::BindGroup, wgpu::BindGroup) {
+        let gbuffer_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("G-buffer bind group"),
+            layout: gbuffer_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&position.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&normal.view),
+                },
+            ],
+        });
+        let raw_occlusion_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("raw occlusion bind group"),
+            layout: occlusion_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&raw_occlusion.view),
+            }],
+        });
+        (gbuffer_bind_group, raw_occlusion_bind_group)
+    }
+
+    // attachments for the prepass: camera relative position, world normal and depth.
+    pub fn gbuffer_views(&self) -> [&wgpu::TextureView; 3] {
+        [&self.position.view, &self.normal.view, &self.depth.view]
+    }
+
+    // 1 where the ambient light is unoccluded, in the output resolution.
+    pub fn occlusion_view(&self) -> &wgpu::TextureView {
+        &self.occlusion.view
+    }
+
+    pub fn render(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        camera: &crate::model::Camera,
+        camera_matrix: Mat4,
+        radius: f32,
+        strength: f32,
+    ) {
+        self.uniform.write(
+            queue,
+            0,
+            &[
+                bytemuck::cast_slice(&[camera_matrix]),
+                bytemuck::cast_slice(&[camera.eye()]),
+                bytemuck::cast_slice(&[camera.direction()]),
+                bytemuck::cast_slice(&[radius]),
+                bytemuck::cast_slice(&[strength]),
+            ],
+        );
+
+        let mut render_pass = Self::begin_render_pass(command_encoder, &self.raw_occlusion.view);
+        render_pass.set_pipeline(&self.ssao_pipeline);
+        self.uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.gbuffer_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        drop(render_pass);
+
+        let mut render_pass = Self::begin_render_pass(command_encoder, &self.occlusion.view);
+        render_pass.set_pipeline(&self.blur_pipeline);
+        self.uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.gbuffer_bind_group, &[]);
+        render_pass.set_bind_group(2, &self.raw_occlusion_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn begin_render_pass<'a>(
+        command_encoder: &'a mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) -> wgpu::RenderPass<'a> {
+        command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("SSAO"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,293 @@
+use crate::renderer::UniformGroup;
+
+// longest text `TextOverlay::render` draws, the rest is cut off. matches `text` in text.wgsl.
+const MAX_LENGTH: usize = 32;
+
+// 5x7 bitmap glyphs. only the characters the frame time and exposure readout needs, others draw as a space.
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT as usize]); 21] = [
+    (
+        ' ',
+        [
+            "     ", "     ", "     ", "     ", "     ", "     ", "     ",
+        ],
+    ),
+    (
+        '0',
+        [
+            " ### ", "#   #", "#  ##", "# # #", "##  #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '1',
+        [
+            "  #  ", " ##  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### ",
+        ],
+    ),
+    (
+        '2',
+        [
+            " ### ", "#   #", "    #", "   # ", "  #  ", " #   ", "#####",
+        ],
+    ),
+    (
+        '3',
+        [
+            " ### ", "#   #", "    #", "  ## ", "    #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '4',
+        [
+            "   # ", "  ## ", " # # ", "#  # ", "#####", "   # ", "   # ",
+        ],
+    ),
+    (
+        '5',
+        [
+            "#####", "#    ", "#### ", "    #", "    #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '6',
+        [
+            "  ## ", " #   ", "#    ", "#### ", "#   #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '7',
+        [
+            "#####", "    #", "   # ", "  #  ", " #   ", " #   ", " #   ",
+        ],
+    ),
+    (
+        '8',
+        [
+            " ### ", "#   #", "#   #", " ### ", "#   #", "#   #", " ### ",
+        ],
+    ),
+    (
+        '9',
+        [
+            " ### ", "#   #", "#   #", " ####", "    #", "   # ", " ##  ",
+        ],
+    ),
+    (
+        '.',
+        [
+            "     ", "     ", "     ", "     ", "     ", " ##  ", " ##  ",
+        ],
+    ),
+    (
+        '+',
+        [
+            "     ", "  #  ", "  #  ", "#####", "  #  ", "  #  ", "     ",
+        ],
+    ),
+    (
+        '-',
+        [
+            "     ", "     ", "     ", "#####", "     ", "     ", "     ",
+        ],
+    ),
+    (
+        'E',
+        [
+            "#####", "#    ", "#    ", "#### ", "#    ", "#    ", "#####",
+        ],
+    ),
+    (
+        'F',
+        [
+            "#####", "#    ", "#    ", "#### ", "#    ", "#    ", "#    ",
+        ],
+    ),
+    (
+        'P',
+        [
+            "#### ", "#   #", "#   #", "#### ", "#    ", "#    ", "#    ",
+        ],
+    ),
+    (
+        'S',
+        [
+            " ####", "#    ", "#    ", " ### ", "    #", "    #", "#### ",
+        ],
+    ),
+    (
+        'V',
+        [
+            "#   #", "#   #", "#   #", "#   #", "#   #", " # # ", "  #  ",
+        ],
+    ),
+    (
+        'm',
+        [
+            "     ", "     ", "## # ", "# # #", "# # #", "# # #", "# # #",
+        ],
+    ),
+    (
+        's',
+        [
+            "     ", "     ", " ####", "#    ", " ### ", "    #", "#### ",
+        ],
+    ),
+];
+
+// a line of text in the top left corner of the output, on a dark background.
+// draws one quad per character from a font atlas with the glyphs side by side.
+pub struct TextOverlay {
+    uniform: UniformGroup,
+    pipeline: wgpu::RenderPipeline,
+    atlas_bind_group: wgpu::BindGroup,
+}
+
+impl TextOverlay {
+    // `format` is the output format.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let mut uniform = UniformGroup::new(
+            device,
+            &[
+                size_of::<[f32; 2]>() as u64,
+                size_of::<[u32; MAX_LENGTH]>() as u64,
+            ],
+        );
+        uniform.add_bind_group(device);
+
+        let atlas_width = GLYPH_WIDTH * GLYPHS.len() as u32;
+        let mut pixels = vec![0u8; (4 * atlas_width * GLYPH_HEIGHT) as usize];
+        for (i, (_, rows)) in GLYPHS.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for (x, bit) in row.bytes().enumerate() {
+                    if bit == b'#' {
+                        let offset = 4 * (y * atlas_width as usize + i * GLYPH_WIDTH as usize + x);
+                        pixels[offset..offset + 4].fill(255);
+                    }
+                }
+            }
+        }
+        let atlas = crate::texture::Texture::from_rgba8(
+            device,
+            queue,
+            atlas_width,
+            GLYPH_HEIGHT,
+            &pixels,
+            wgpu::TextureFormat::Rgba8Unorm,
+            None,
+            Some("font atlas"),
+        );
+
+        let atlas_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Font atlas bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+        let atlas_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Font atlas bind group"),
+            layout: &atlas_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&atlas.view),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/text.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&uniform.bind_group_layout, &atlas_bind_group_layout],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_text"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_text"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        Self {
+            uniform,
+            pipeline,
+            atlas_bind_group,
+        }
+    }
+
+    // draws `text` over `output_view`, which is `width` x `height`.
+    pub fn render(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        output_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+        text: &str,
+    ) {
+        let mut glyphs = [0u32; MAX_LENGTH];
+        let mut length = 0;
+        for (glyph, char) in glyphs.iter_mut().zip(text.chars()) {
+            *glyph = GLYPHS
+                .iter()
+                .position(|(glyph_char, _)| *glyph_char == char)
+                .unwrap_or(0) as u32;
+            length += 1;
+        }
+        self.uniform.write(
+            queue,
+            0,
+            &[
+                bytemuck::cast_slice(&[width as f32, height as f32]),
+                bytemuck::cast_slice(&glyphs),
+            ],
+        );
+
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Text overlay"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output_view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        self.uniform.set(&mut render_pass, 0, 0);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        render_pass.draw(0..4, 0..length);
+    }
+}
@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+
 use image::GenericImageView;
 
+// the highest `anisotropy_clamp` wgpu accepts.
+pub const MAX_ANISOTROPY: u16 = 16;
+
 #[allow(unused)]
 pub struct Texture {
     pub texture: wgpu::Texture,
@@ -13,20 +18,54 @@ impl Texture {
         let rgba8 = image.to_rgba8();
 
         let dimensions = image.dimensions();
+        Self::from_rgba8(
+            device,
+            queue,
+            dimensions.0,
+            dimensions.1,
+            &rgba8,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            None,
+            Some("icon texture"),
+        )
+    }
+
+    // texture from tightly packed RGBA8 pixels.
+    // `format` should be an RGBA8 format; pick the sRGB one for color data.
+    // with `mipmaps` the full mip chain is filled in from the pixels by its next `submit`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        format: wgpu::TextureFormat,
+        mipmaps: Option<&mut MipmapGenerator>,
+        label: Option<&str>,
+    ) -> Self {
         let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
+            width,
+            height,
             depth_or_array_layers: 1,
         };
 
+        let (mip_level_count, usage) = if mipmaps.is_some() {
+            (
+                size.max_mips(wgpu::TextureDimension::D2),
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+        } else {
+            (1, wgpu::TextureUsages::empty())
+        };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("icon texture"),
+            label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | usage,
             view_formats: &[],
         });
         queue.write_texture(
@@ -36,39 +75,165 @@ impl Texture {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            bytemuck::cast_slice(&rgba8),
+            pixels,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
             },
             size,
         );
+        if let Some(mipmaps) = mipmaps
+            && mip_level_count > 1
+        {
+            mipmaps.pending.push(texture.clone());
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         Self { texture, view }
     }
 
+    // trilinear sampler, anisotropic up to `anisotropy_clamp` samples (clamped to 1..=MAX_ANISOTROPY).
+    // backends without `DownlevelFlags::ANISOTROPIC_FILTERING` ignore the clamp.
+    pub fn create_sampler(
+        device: &wgpu::Device,
+        address_mode: wgpu::AddressMode,
+        anisotropy_clamp: u16,
+        label: Option<&str>,
+    ) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::MipmapFilterMode::Linear,
+            // textures without mipmaps only have level 0, which clamps anyway.
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            anisotropy_clamp: anisotropy_clamp.clamp(1, MAX_ANISOTROPY),
+            ..Default::default()
+        })
+    }
+
+    // sampler for a material texture, anisotropic like `create_sampler` when it's filtered linearly.
+    pub fn create_material_sampler(
+        device: &wgpu::Device,
+        sampler: &crate::model::Sampler,
+        anisotropy_clamp: u16,
+    ) -> wgpu::Sampler {
+        let linear = sampler.mag_filter == wgpu::FilterMode::Linear
+            && sampler.min_filter == wgpu::FilterMode::Linear
+            && sampler.mipmap_filter == Some(wgpu::MipmapFilterMode::Linear);
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("material sampler"),
+            address_mode_u: sampler.address_mode_u,
+            address_mode_v: sampler.address_mode_v,
+            mag_filter: sampler.mag_filter,
+            min_filter: sampler.min_filter,
+            mipmap_filter: sampler
+                .mipmap_filter
+                .unwrap_or(wgpu::MipmapFilterMode::Nearest),
+            lod_min_clamp: 0.0,
+            lod_max_clamp: if sampler.mipmap_filter.is_some() {
+                32.0
+            } else {
+                0.0
+            },
+            // anisotropic filtering is only valid with linear filtering.
+            anisotropy_clamp: if linear {
+                anisotropy_clamp.clamp(1, MAX_ANISOTROPY)
+            } else {
+                1
+            },
+            ..Default::default()
+        })
+    }
+
+    // sRGB cube map from six tightly packed RGBA8 faces of `size`^2, in the order +X, -X, +Y, -Y, +Z, -Z.
+    pub fn cube_from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: u32,
+        faces: [&[u8]; 6],
+        label: Option<&str>,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        for (layer, pixels) in faces.into_iter().enumerate() {
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                pixels,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * size),
+                    rows_per_image: Some(size),
+                },
+                wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        Self { texture, view }
+    }
+
     pub fn create_depth_texture(
         device: &wgpu::Device,
         width: u32,
         height: u32,
         layers: u32,
+        sample_count: u32,
     ) -> Self {
         let size = wgpu::Extent3d {
             width,
             height,
             depth_or_array_layers: layers,
         };
+        // multisampled depth is only ever used as an attachment.
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage,
             view_formats: &[wgpu::TextureFormat::Depth32Float],
         });
 
@@ -76,4 +241,306 @@ impl Texture {
 
         Self { texture, view }
     }
+
+    // `cubes` depth cube maps of `size`^2 faces, viewed as a cube array. face `f` of cube `i` is layer `6 * i + f`.
+    pub fn create_depth_cube_texture(device: &wgpu::Device, size: u32, cubes: u32) -> Self {
+        let Self { texture, .. } = Self::create_depth_texture(device, size, size, 6 * cubes, 1);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::CubeArray),
+            ..Default::default()
+        });
+
+        Self { texture, view }
+    }
+
+    // color attachment for intermediate passes. multisampled targets are only resolved, never sampled.
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        label: Option<&str>,
+    ) -> Self {
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    // color attachment that can be copied back to the CPU with `read_rgba8`.
+    pub fn create_readback_target(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    // copies the first layer back as tightly packed RGBA8 pixels, blocking until the GPU is done.
+    // the texture needs COPY_SRC and an 8 bit RGBA or BGRA format.
+    pub fn read_rgba8(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<u8>, ReadbackError> {
+        let format = self.texture.format();
+        if !matches!(
+            format,
+            wgpu::TextureFormat::Rgba8Unorm
+                | wgpu::TextureFormat::Rgba8UnormSrgb
+                | wgpu::TextureFormat::Bgra8Unorm
+                | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            return Err(ReadbackError::UnsupportedFormat(format));
+        }
+        let width = self.texture.width();
+        let height = self.texture.height();
+
+        // rows of a texture to buffer copy are padded to a 256 byte alignment.
+        let row_bytes = 4 * width;
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback buffer"),
+            size: (padded_row_bytes * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        command_encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(command_encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, mapped) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(ReadbackError::Poll)?;
+        // the callback has run once the poll is done.
+        mapped
+            .recv()
+            .expect("the readback buffer wasn't mapped")
+            .map_err(ReadbackError::Map)?;
+
+        let mut pixels = Vec::with_capacity((row_bytes * height) as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks_exact(padded_row_bytes as usize)
+        {
+            pixels.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        if matches!(
+            format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+// fills the mip levels 1.. of textures by repeatedly rendering a bilinear downsample of the
+// level above. sRGB textures are filtered in linear space, the views decode and encode.
+// keeps a pipeline per texture format, so it's meant to be reused across uploads.
+pub struct MipmapGenerator {
+    shader: wgpu::ShaderModule,
+    sampler: wgpu::Sampler,
+    pipelines: HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+    // textures from `Texture::from_rgba8` waiting for `submit`.
+    pending: Vec<wgpu::Texture>,
+}
+
+impl MipmapGenerator {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            shader: device.create_shader_module(wgpu::include_wgsl!("shader/mipmap.wgsl")),
+            sampler: Texture::create_sampler(
+                device,
+                wgpu::AddressMode::ClampToEdge,
+                1,
+                Some("mipmap sampler"),
+            ),
+            pipelines: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    // generates the mipmaps of every pending texture in one submission.
+    pub fn submit(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("mipmap encoder"),
+        });
+        for texture in std::mem::take(&mut self.pending) {
+            self.encode(device, &mut encoder, &texture);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+
+    fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        let shader = &self.shader;
+        let pipeline = self.pipelines.entry(texture.format()).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("mipmap"),
+                layout: None,
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_full"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some("fs_downsample"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(texture.format().into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        });
+        let bind_group_layout = pipeline.get_bind_group_layout(0);
+
+        let views: Vec<wgpu::TextureView> = (0..texture.mip_level_count())
+            .map(|mip_level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: mip_level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        for pair in views.windows(2) {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&pair[0]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("mipmap pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &pair[1],
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadbackError {
+    // only 8 bit RGBA and BGRA textures are read back.
+    UnsupportedFormat(wgpu::TextureFormat),
+    Map(wgpu::BufferAsyncError),
+    Poll(wgpu::PollError),
+}
+
+impl core::fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadbackError::UnsupportedFormat(format) => {
+                write!(f, "can't read back {format:?} as RGBA8")
+            }
+            ReadbackError::Map(err) => write!(f, "failed to map the readback buffer: {err}"),
+            ReadbackError::Poll(err) => write!(f, "failed to wait for the readback: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ReadbackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadbackError::UnsupportedFormat(_) => None,
+            ReadbackError::Map(err) => Some(err),
+            ReadbackError::Poll(err) => Some(err),
+        }
+    }
 }
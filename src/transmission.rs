@@ -0,0 +1,261 @@
+use crate::renderer::HDR_FORMAT;
+
+// the roughest transmissive surfaces see the scene through the last of these half resolution steps.
+const MIP_COUNT: u32 = 6;
+
+// a copy of the HDR image with everything drawn before the transmissive surfaces, blurred into
+// a mip chain. transmissive surfaces sample it behind them, rougher ones from smaller mips.
+pub(crate) struct Transmission {
+    source_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+
+    copy_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+
+    // every mip, for the lit shader.
+    view: wgpu::TextureView,
+    mip_views: Vec<wgpu::TextureView>,
+    // reads the HDR texture, for the copy into mip 0.
+    hdr_bind_group: wgpu::BindGroup,
+    // one per mip, reading that mip.
+    mip_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl Transmission {
+    pub fn new(
+        device: &wgpu::Device,
+        hdr_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let source_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Transmission source bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        // also used by the lit shader, blending between mips for roughness in between.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("transmission sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::MipmapFilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader/transmission.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&source_bind_group_layout],
+            immediate_size: 0,
+        });
+        let create_pipeline = |label, entry_point| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_full"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview_mask: None,
+                cache: None,
+            })
+        };
+        let copy_pipeline = create_pipeline("Transmission copy", "fs_transmission_copy");
+        let downsample_pipeline =
+            create_pipeline("Transmission downsample", "fs_transmission_downsample");
+
+        let hdr_bind_group =
+            Self::create_source_bind_group(device, &source_bind_group_layout, &sampler, hdr_view);
+        let (view, mip_views, mip_bind_groups) =
+            Self::create_mips(device, &source_bind_group_layout, &sampler, width, height);
+
+        Self {
+            source_bind_group_layout,
+            sampler,
+            copy_pipeline,
+            downsample_pipeline,
+            view,
+            mip_views,
+            hdr_bind_group,
+            mip_bind_groups,
+        }
+    }
+
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        hdr_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        self.hdr_bind_group = Self::create_source_bind_group(
+            device,
+            &self.source_bind_group_layout,
+            &self.sampler,
+            hdr_view,
+        );
+        (self.view, self.mip_views, self.mip_bind_groups) = Self::create_mips(
+            device,
+            &self.source_bind_group_layout,
+            &self.sampler,
+            width,
+            height,
+        );
+    }
+
+    // the scene behind the transmissive surfaces, sharp in mip 0.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    fn create_source_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("transmission source bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    fn create_mips(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+    ) -> (
+        wgpu::TextureView,
+        Vec<wgpu::TextureView>,
+        Vec<wgpu::BindGroup>,
+    ) {
+        let size = wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("transmission"),
+            size,
+            mip_level_count: MIP_COUNT.min(size.max_mips(wgpu::TextureDimension::D2)),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&Default::default());
+        let mip_views: Vec<wgpu::TextureView> = (0..texture.mip_level_count())
+            .map(|mip| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        let bind_groups = mip_views
+            .iter()
+            .map(|view| Self::create_source_bind_group(device, layout, sampler, view))
+            .collect();
+        (view, mip_views, bind_groups)
+    }
+
+    // copies the HDR image as drawn so far and blurs it down the mip chain.
+    // call between render passes, the HDR texture can't be sampled while it's being drawn to.
+    pub fn capture(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        self.render_pass(
+            command_encoder,
+            &self.copy_pipeline,
+            &self.hdr_bind_group,
+            0,
+        );
+        for mip in 1..self.mip_views.len() {
+            self.render_pass(
+                command_encoder,
+                &self.downsample_pipeline,
+                &self.mip_bind_groups[mip - 1],
+                mip,
+            );
+        }
+    }
+
+    fn render_pass(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::BindGroup,
+        target_mip: usize,
+    ) {
+        let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Transmission"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.mip_views[target_mip],
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, source, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::{Arc, Mutex};
+
+use glam::Vec3;
+use pbrpbr::model::{Light, Mesh, Sun};
+use pbrpbr::{Renderer, Scene, Texture};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// validation errors of a device, collected instead of panicking.
+type Errors = Arc<Mutex<Vec<String>>>;
+
+// None when there's no adapter to render with, then the tests are skipped.
+fn device() -> Option<(wgpu::Device, wgpu::Queue, Errors, wgpu::Backend)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::from_env().unwrap_or(wgpu::Backends::all()),
+        ..Default::default()
+    });
+    let adapter =
+        match pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        {
+            Ok(adapter) => adapter,
+            Err(err) => {
+                eprintln!("skipped, {err}");
+                return None;
+            }
+        };
+    let (device, queue) =
+        match pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())) {
+            Ok(device) => device,
+            Err(err) => {
+                eprintln!("skipped, {err}");
+                return None;
+            }
+        };
+
+    let errors = Errors::default();
+    let reported = errors.clone();
+    device.on_uncaptured_error(Arc::new(move |err| {
+        reported.lock().unwrap().push(format!("{err:?}"));
+    }));
+    Some((device, queue, errors, adapter.get_info().backend))
+}
+
+// a lit cube in front of the camera, on the default black background.
+fn scene() -> Scene {
+    let mut scene = Scene::new();
+    scene.add_mesh(Mesh::cube());
+    scene.add_light(Light::Point {
+        pos: Vec3::new(0.0, 0.0, 2.0),
+        color: Vec3::ONE,
+        radiant_flux: 100.0,
+    });
+    scene.camera.position = Vec3::new(0.0, 0.0, 3.0);
+    scene
+}
+
+fn renderer(device: &wgpu::Device, queue: &wgpu::Queue) -> Renderer {
+    let surface_configuration = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: FORMAT,
+        width: WIDTH,
+        height: HEIGHT,
+        present_mode: wgpu::PresentMode::Fifo,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+        view_formats: vec![],
+    };
+    Renderer::new(device, queue, &surface_configuration, 1)
+}
+
+fn pixel(pixels: &[u8], x: u32, y: u32) -> [u8; 4] {
+    let i = 4 * (y * WIDTH + x) as usize;
+    pixels[i..i + 4].try_into().unwrap()
+}
+
+#[test]
+fn renders_a_frame() {
+    let Some((device, queue, errors, _)) = device() else {
+        return;
+    };
+    let scene = scene();
+    let mut renderer = renderer(&device, &queue);
+    renderer.upload_textures(&device, &queue, &scene);
+
+    let target = Texture::create_readback_target(&device, WIDTH, HEIGHT, FORMAT, None);
+    renderer.render_to(&device, &queue, &scene, &target.view);
+    let pixels = target.read_rgba8(&device, &queue).unwrap();
+    assert_eq!(pixels.len(), (4 * WIDTH * HEIGHT) as usize);
+
+    // the cube covers the center, the corners show the background.
+    let center = pixel(&pixels, WIDTH / 2, HEIGHT / 2);
+    let corner = pixel(&pixels, 0, 0);
+    let brightness = |pixel: [u8; 4]| pixel[..3].iter().map(|&c| u32::from(c)).sum::<u32>();
+    assert!(
+        brightness(center) > brightness(corner) + 96,
+        "center {center:?}, corner {corner:?}"
+    );
+    assert_eq!(*errors.lock().unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn reading_back_hdr_fails() {
+    let Some((device, queue, _, _)) = device() else {
+        return;
+    };
+    let target = Texture::create_readback_target(&device, WIDTH, HEIGHT, pbrpbr::HDR_FORMAT, None);
+    assert!(matches!(
+        target.read_rgba8(&device, &queue),
+        Err(pbrpbr::ReadbackError::UnsupportedFormat(pbrpbr::HDR_FORMAT))
+    ));
+}
+
+// the brightness variance of a frame of a wide plane under a low sun, at the default shadow
+// biases or without any.
+fn sun_on_plane_variance(device: &wgpu::Device, queue: &wgpu::Queue, unbiased: bool) -> f32 {
+    let mut scene = Scene::new();
+    let mut plane = Mesh::plane();
+    plane.transform.scale = Vec3::splat(20.0);
+    scene.add_mesh(plane);
+    scene.sun = Some(Sun {
+        elevation: 0.2,
+        ..Default::default()
+    });
+    scene.camera.position = Vec3::new(0.0, 2.0, 2.0);
+    scene.camera.pitch = -std::f32::consts::FRAC_PI_4;
+
+    let mut renderer = renderer(device, queue);
+    if unbiased {
+        renderer.shadow_bias = 0.0;
+        renderer.shadow_normal_bias = 0.0;
+    }
+    renderer.upload_textures(device, queue, &scene);
+    let target = Texture::create_readback_target(device, WIDTH, HEIGHT, FORMAT, None);
+    renderer.render_to(device, queue, &scene, &target.view);
+    let pixels = target.read_rgba8(device, queue).unwrap();
+
+    let brightness: Vec<f32> = pixels
+        .chunks_exact(4)
+        .map(|pixel| pixel[..3].iter().map(|&c| f32::from(c)).sum())
+        .collect();
+    let mean = brightness.iter().sum::<f32>() / brightness.len() as f32;
+    brightness.iter().map(|b| (b - mean).powi(2)).sum::<f32>() / brightness.len() as f32
+}
+
+#[test]
+fn shadow_bias_removes_acne() {
+    let Some((device, queue, errors, backend)) = device() else {
+        return;
+    };
+    // GL samples directional shadow maps as fully shadowed, there's no acne to compare.
+    if backend == wgpu::Backend::Gl {
+        eprintln!("skipped on GL");
+        return;
+    }
+    // a plane the sun grazes shadows itself in stripes without a bias.
+    let unbiased = sun_on_plane_variance(&device, &queue, true);
+    let biased = sun_on_plane_variance(&device, &queue, false);
+    assert!(biased < unbiased, "biased {biased}, unbiased {unbiased}");
+    assert_eq!(*errors.lock().unwrap(), Vec::<String>::new());
+}